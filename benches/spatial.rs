@@ -0,0 +1,71 @@
+//! Compares `SpatialGrid` lookups with scanning every house, for growing numbers of houses
+//! spread at the same density as the builtin maps. Run with `cargo bench --bench spatial`.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use bevy::utils::FloatOrd;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use parallel_paperboy::spatial::{Shape, SpatialGrid, CELL_SIZE};
+
+const HOUSE_COUNTS: [usize; 5] = [100, 1_000, 10_000, 50_000, 100_000];
+const QUERIES: usize = 10_000;
+// roughly one house per this many square world units
+const AREA_PER_HOUSE: f32 = 55. * 70.;
+const REACH: f32 = 120.0;
+const K: usize = 5;
+
+fn per_query(elapsed: Duration) -> f64 {
+    elapsed.as_secs_f64() * 1e6 / QUERIES as f64
+}
+
+fn main() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    println!("{:>8} {:>10} {:>12} {:>12} {:>12} {:>12}", "houses", "build ms", "scan us", "nearest us", "k=5 us", "radius us");
+    for count in HOUSE_COUNTS {
+        let side = (count as f32 * AREA_PER_HOUSE).sqrt();
+        let mut random_point = || Vec2::new(rng.gen_range(0.0..side), rng.gen_range(0.0..side));
+        let houses: Vec<Vec2> = (0..count).map(|_| random_point()).collect();
+        let queries: Vec<Vec2> = (0..QUERIES).map(|_| random_point()).collect();
+
+        let start = Instant::now();
+        let mut grid = SpatialGrid::new(CELL_SIZE);
+        for (index, house) in houses.iter().enumerate() {
+            grid.insert(index, Shape::Point(*house));
+        }
+        let build = start.elapsed();
+
+        let start = Instant::now();
+        let scanned: Vec<usize> = queries.iter()
+            .map(|query| (0..count).min_by_key(|index| FloatOrd(houses[*index].distance(*query))).unwrap())
+            .collect();
+        let scan = start.elapsed();
+
+        let start = Instant::now();
+        let nearest: Vec<usize> = queries.iter().map(|query| grid.nearest(*query, 1, |_| true)[0].0).collect();
+        let nearest_time = start.elapsed();
+        // ties aside, the grid has to agree with the scan
+        let agree = scanned.iter().zip(&nearest).zip(&queries)
+            .all(|((a, b), query)| houses[*a].distance(*query) == houses[*b].distance(*query));
+        assert!(agree, "grid and scan disagree on the nearest house");
+
+        let start = Instant::now();
+        for query in &queries {
+            std::hint::black_box(grid.nearest(*query, K, |_| true));
+        }
+        let k_nearest = start.elapsed();
+
+        let start = Instant::now();
+        for query in &queries {
+            std::hint::black_box(grid.within(*query, REACH));
+        }
+        let radius = start.elapsed();
+
+        println!(
+            "{:>8} {:>10.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2}",
+            count, build.as_secs_f64() * 1e3, per_query(scan), per_query(nearest_time), per_query(k_nearest), per_query(radius),
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::window::PrimaryWindow;
+
+use crate::input::{Action, ActionState};
+use crate::{BOTTOM_WALL, LEFT_WALL, RIGHT_WALL, TOP_WALL};
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+// fraction of the current zoom applied per scroll line
+const ZOOM_STEP: f32 = 0.1;
+// scroll lines per pixel for touchpads reporting in pixels
+const PIXELS_PER_LINE: f32 = 20.0;
+// screen pixels per second, scaled by zoom so panning feels the same at every zoom level
+const CAMERA_PAN_SPEED: f32 = 400.0;
+
+/// Used to help identify our main camera
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Cursor position in world coordinates, refreshed every frame after the camera has moved
+#[derive(Resource, Default, Debug)]
+pub struct CursorWorldPosition(pub Option<Vec2>);
+
+/// Where `CursorWorldPosition` is refreshed. Only the windowed game has systems in it, but the
+/// simulation's mouse systems are ordered after it either way
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CursorUpdate;
+
+/// Whether the camera keeps the selected paperboy centered
+#[derive(Resource, Default)]
+pub struct CameraFollow(pub bool);
+
+/// Converts a window cursor position to world coordinates under the given camera.
+///
+/// Worked out from the camera's `Transform` and projection directly, rather than
+/// `Camera::viewport_to_world`, because the camera's cached `GlobalTransform` and projection
+/// matrix are only refreshed in `PostUpdate` and would lag a frame behind a pan or zoom.
+pub fn cursor_to_world(
+    window: &Window,
+    camera_transform: &Transform,
+    projection: &OrthographicProjection,
+) -> Option<Vec2> {
+    // bevy reports the cursor from the bottom left corner of the window
+    let cursor = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+    let offset = (cursor - window_size * projection.viewport_origin) * projection.scale;
+    Some(camera_transform.transform_point(offset.extend(0.)).truncate())
+}
+
+fn clamp_to_map(translation: &mut Vec3) {
+    translation.x = translation.x.clamp(LEFT_WALL, RIGHT_WALL);
+    translation.y = translation.y.clamp(BOTTOM_WALL, TOP_WALL);
+}
+
+pub fn zoom_camera(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera_q: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut scroll_evr: EventReader<MouseWheel>,
+) {
+    let lines: f32 = scroll_evr.iter().map(|ev| match ev.unit {
+        MouseScrollUnit::Line => ev.y,
+        MouseScrollUnit::Pixel => ev.y / PIXELS_PER_LINE,
+    }).sum();
+    if lines == 0. {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else { return };
+    let (mut transform, mut projection) = camera_q.single_mut();
+
+    let old_scale = projection.scale;
+    let new_scale = (old_scale * (1. - lines * ZOOM_STEP)).clamp(MIN_ZOOM, MAX_ZOOM);
+
+    // keep the world point under the cursor fixed while zooming
+    if let Some(anchor) = cursor_to_world(window, &transform, &projection) {
+        let from_anchor = transform.translation.truncate() - anchor;
+        let new_position = anchor + from_anchor * (new_scale / old_scale);
+        transform.translation.x = new_position.x;
+        transform.translation.y = new_position.y;
+    }
+    projection.scale = new_scale;
+    clamp_to_map(&mut transform.translation);
+}
+
+pub fn pan_camera(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    mut follow: ResMut<CameraFollow>,
+    mut camera_q: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+    mut motion_evr: EventReader<MouseMotion>,
+) {
+    let mut pan = Vec2::ZERO;
+
+    if actions.pressed(Action::DragCamera) {
+        // mouse motion is reported with y pointing down the screen
+        for ev in motion_evr.iter() {
+            pan += Vec2::new(-ev.delta.x, ev.delta.y);
+        }
+    } else {
+        motion_evr.clear();
+    }
+
+    let mut direction = Vec2::ZERO;
+    if actions.pressed(Action::PanLeft) { direction.x -= 1.; }
+    if actions.pressed(Action::PanRight) { direction.x += 1.; }
+    if actions.pressed(Action::PanDown) { direction.y -= 1.; }
+    if actions.pressed(Action::PanUp) { direction.y += 1.; }
+    // real time, so the camera can still be moved around while the game is paused
+    pan += direction.normalize_or_zero() * CAMERA_PAN_SPEED * time.raw_delta_seconds();
+
+    if pan == Vec2::ZERO {
+        return;
+    }
+
+    // panning by hand takes the camera off the paperboy
+    follow.0 = false;
+
+    let (mut transform, projection) = camera_q.single_mut();
+    let world_pan = transform.rotation * (pan * projection.scale).extend(0.);
+    transform.translation += world_pan;
+    clamp_to_map(&mut transform.translation);
+}
+
+pub fn toggle_camera_follow(actions: Res<ActionState>, mut follow: ResMut<CameraFollow>) {
+    if actions.just_pressed(Action::ToggleFollow) {
+        follow.0 = !follow.0;
+        debug!("follow pressed, camera follow is now {:?}", follow.0);
+    }
+}
+
+type SelectedPaperboy = (With<crate::Paperboy>, With<crate::Selected>, Without<MainCamera>);
+
+pub fn follow_selected_paperboy(
+    follow: Res<CameraFollow>,
+    selected: Query<&Transform, SelectedPaperboy>,
+    mut camera_q: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !follow.0 {
+        return;
+    }
+    let Ok(paperboy) = selected.get_single() else { return };
+    let mut transform = camera_q.single_mut();
+    transform.translation.x = paperboy.translation.x;
+    transform.translation.y = paperboy.translation.y;
+    clamp_to_map(&mut transform.translation);
+}
+
+pub fn update_cursor_world_position(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_q: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut cursor: ResMut<CursorWorldPosition>,
+) {
+    let Ok(window) = windows.get_single() else {
+        cursor.0 = None;
+        return;
+    };
+    let (transform, projection) = camera_q.single();
+    cursor.0 = cursor_to_world(window, transform, projection);
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy::utils::petgraph::graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+
+use crate::graph::{self, GameWorld};
+use crate::levels::CurrentLevel;
+use crate::movement::Route;
+use crate::routing::Reroute;
+use crate::shift::Shift;
+use crate::spatial::Shape;
+
+// across the road, and a little wider than it
+const BARRIER_SIZE: Vec2 = Vec2::new(6., 30.);
+// a route passing this close to a barrier runs into it
+const BARRIER_RADIUS: f32 = 15.0;
+const CONSTRUCTION_COLOR: Color = Color::rgb(1.0, 0.55, 0.0);
+const PARADE_COLOR: Color = Color::rgb(0.7, 0.1, 0.8);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ClosureKind {
+    Construction,
+    Parade,
+}
+
+/// A road shut in both directions for part of a shift
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub kind: ClosureKind,
+    // the nodes at either end, as indices into the map's `graph.nodes`
+    pub edge: (usize, usize),
+    // seconds into the shift
+    pub from: f32,
+    pub to: f32,
+}
+
+impl Closure {
+    pub fn is_active(&self, elapsed: f32) -> bool {
+        self.from <= elapsed && elapsed < self.to
+    }
+
+    /// The nodes at either end, lowest index first
+    fn nodes(&self) -> (usize, usize) {
+        (self.edge.0.min(self.edge.1), self.edge.0.max(self.edge.1))
+    }
+}
+
+/// Drawn across the middle of a closed road, and what routes run into
+#[derive(Component)]
+pub struct Barrier {
+    nodes: (usize, usize),
+}
+
+/// Whether walking from `position` along `waypoints` runs into a barrier at one of `barriers`.
+/// One the paperboy is already at doesn't count, so it can walk away from it.
+pub fn blocked(position: Vec2, waypoints: &[Vec2], barriers: &[Vec2]) -> bool {
+    let ahead: Vec<Vec2> = barriers.iter().copied().filter(|barrier| barrier.distance(position) > BARRIER_RADIUS).collect();
+    let mut from = position;
+    for to in waypoints {
+        if ahead.iter().any(|barrier| Shape::Segment(from, *to).distance(*barrier) < BARRIER_RADIUS) {
+            return true;
+        }
+        from = *to;
+    }
+    false
+}
+
+fn spawn_barrier(commands: &mut Commands, world: &GameWorld, closure: &Closure) -> Option<Entity> {
+    let (start, end) = closure.nodes();
+    let (start, end) = (world.graph.node_weight(NodeIndex::new(start))?, world.graph.node_weight(NodeIndex::new(end))?);
+    let (start, end) = (graph::world_position(start), graph::world_position(end));
+    let along = end - start;
+    let color = match closure.kind {
+        ClosureKind::Construction => CONSTRUCTION_COLOR,
+        ClosureKind::Parade => PARADE_COLOR,
+    };
+    Some(commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                ..default()
+            },
+            transform: Transform {
+                // over the road it closes
+                translation: start.lerp(end, 0.5).extend(0.5),
+                rotation: Quat::from_rotation_z(along.y.atan2(along.x)),
+                scale: BARRIER_SIZE.extend(1.0),
+            },
+            ..default()
+        },
+        Barrier { nodes: closure.nodes() },
+    )).id())
+}
+
+/// Closes and reopens roads as the shift goes on, putting up and taking down their barriers
+pub fn update_closures(
+    mut commands: Commands,
+    current: Res<CurrentLevel>,
+    shift: Res<Shift>,
+    mut world: ResMut<GameWorld>,
+    barriers: Query<(Entity, &Barrier)>,
+) {
+    let elapsed = shift.timer.elapsed_secs();
+    let active: Vec<&Closure> = current.level.closures.iter().filter(|closure| closure.is_active(elapsed)).collect();
+    let is_closed = |start: NodeIndex, end: NodeIndex| {
+        let nodes = (start.index().min(end.index()), start.index().max(end.index()));
+        active.iter().any(|closure| closure.nodes() == nodes)
+    };
+
+    // only touch the world when something changes, as everything routing off it rebuilds then
+    let changed: Vec<_> = world.graph.edge_indices()
+        .filter_map(|edge| world.graph.edge_endpoints(edge).map(|(start, end)| (edge, is_closed(start, end))))
+        .filter(|(edge, closed)| world.graph[*edge].closed != *closed)
+        .collect();
+    for (edge, closed) in changed {
+        world.graph[edge].closed = closed;
+    }
+
+    for (entity, barrier) in &barriers {
+        if !active.iter().any(|closure| closure.nodes() == barrier.nodes) {
+            debug!("road {:?} reopened", barrier.nodes);
+            commands.entity(entity).despawn();
+        }
+    }
+    for closure in active {
+        if !barriers.iter().any(|(_, barrier)| barrier.nodes == closure.nodes()) {
+            // closures of roads the map doesn't have were reported when the level loaded
+            if spawn_barrier(&mut commands, &world, closure).is_some() {
+                info!("road {:?} closed for {:?}", closure.nodes(), closure.kind);
+            }
+        }
+    }
+}
+
+/// Stops paperboys whose route runs into a barrier, and asks for a way around it
+pub fn stop_blocked_paperboys(
+    mut commands: Commands,
+    mut reroute: EventWriter<Reroute>,
+    barriers: Query<&Transform, With<Barrier>>,
+    paperboys: Query<(Entity, &Transform, &Route), With<crate::Paperboy>>,
+) {
+    let barriers: Vec<Vec2> = barriers.iter().map(|transform| transform.translation.truncate()).collect();
+    if barriers.is_empty() {
+        return;
+    }
+    for (paperboy, transform, route) in &paperboys {
+        let position = transform.translation.truncate();
+        if route.next < route.waypoints.len() && blocked(position, &route.waypoints[route.next..], &barriers) {
+            debug!("paperboy {:?} ran into a closed road, stopping", paperboy);
+            commands.entity(paperboy).remove::<Route>();
+            reroute.send(Reroute {
+                paperboy,
+                waypoints: route.waypoints[route.next..].to_vec(),
+                attempted: route.attempted.clone(),
+            });
+        }
+    }
+}
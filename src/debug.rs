@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+
+use crate::graph::{self, EdgeKind, GameWorld};
+use crate::input::{Action, ActionState};
+
+const DEBUG_LINE_THICKNESS: f32 = 1.5;
+const DEBUG_FONT_SIZE: f32 = 14.0;
+// segments making up a circle
+const CIRCLE_SEGMENTS: usize = 24;
+// above the map, the weather and the shrouds
+const DEBUG_Z: f32 = 10.0;
+const PATH_POINT_SIZE: f32 = 6.0;
+
+// graph view
+const NODE_RADIUS: f32 = 8.0;
+// each direction of a road is drawn this far to its right, so both show
+const EDGE_OFFSET: f32 = 4.0;
+const ARROW_LENGTH: f32 = 8.0;
+const ARROW_ANGLE: f32 = 0.5;
+const LABEL_OFFSET: f32 = 10.0;
+
+const NODE_COLOR: Color = Color::rgb(0.1, 0.5, 1.0);
+const EDGE_COLOR: Color = Color::rgb(0.9, 0.5, 0.0);
+const FOOTPATH_EDGE_COLOR: Color = Color::rgb(0.5, 0.8, 0.0);
+const COLLIDER_COLOR: Color = Color::rgb(0.0, 0.8, 0.3);
+const PATH_POINT_COLOR: Color = Color::rgb(1.0, 0.0, 0.6);
+const REACH_COLOR: Color = Color::rgb(0.6, 0.2, 0.9);
+const TERRITORY_COLOR: Color = Color::rgb(0.45, 0.3, 0.15);
+
+/// What is drawn over the map for debugging: the whole overlay, or only the road graph
+#[derive(Resource, Default, Debug)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    pub graph: bool,
+}
+
+/// Lines and labels to draw this frame. Anything can add to it; it is drawn and emptied at the
+/// end of every frame.
+#[derive(Resource, Default, Debug)]
+pub struct DebugShapes {
+    lines: Vec<(Vec2, Vec2, Color)>,
+    labels: Vec<(Vec2, String, Color)>,
+}
+
+impl DebugShapes {
+    pub fn line(&mut self, from: Vec2, to: Vec2, color: Color) {
+        self.lines.push((from, to, color));
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        let point = |i: usize| {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        };
+        for i in 0..CIRCLE_SEGMENTS {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    /// Outline of a `size` box around `center`, turned by `rotation`
+    pub fn rect(&mut self, center: Vec2, size: Vec2, rotation: Quat, color: Color) {
+        let half = size.abs() / 2.;
+        let corners = [Vec2::new(-1., -1.), Vec2::new(1., -1.), Vec2::new(1., 1.), Vec2::new(-1., 1.)]
+            .map(|corner| center + (rotation * (corner * half).extend(0.)).truncate());
+        for i in 0..corners.len() {
+            self.line(corners[i], corners[(i + 1) % corners.len()], color);
+        }
+    }
+
+    pub fn cross(&mut self, center: Vec2, size: f32, color: Color) {
+        let half = size / 2.;
+        self.line(center - Vec2::splat(half), center + Vec2::splat(half), color);
+        self.line(center + Vec2::new(-half, half), center + Vec2::new(half, -half), color);
+    }
+
+    pub fn label(&mut self, position: Vec2, text: impl Into<String>, color: Color) {
+        self.labels.push((position, text.into(), color));
+    }
+}
+
+/// A pooled sprite drawing one of the overlay's lines
+#[derive(Component)]
+pub struct DebugLine;
+
+/// A pooled text drawing one of the overlay's labels
+#[derive(Component)]
+pub struct DebugLabel;
+
+pub fn toggle_debug_overlay(actions: Res<ActionState>, mut overlay: ResMut<DebugOverlay>) {
+    if actions.just_pressed(Action::DebugOverlay) {
+        overlay.enabled = !overlay.enabled;
+        info!("debug overlay is now {}", if overlay.enabled { "on" } else { "off" });
+    }
+    if actions.just_pressed(Action::GraphView) {
+        overlay.graph = !overlay.graph;
+        info!("graph view is now {}", if overlay.graph { "on" } else { "off" });
+    }
+}
+
+/// The road graph as `GameWorld` has it, not as the road sprites show it: every node as a
+/// circle with its `NodeIndex`, every edge as an arrow the way it points, with its length
+pub fn draw_graph(world: &GameWorld, shapes: &mut DebugShapes) {
+    let graph = &world.graph;
+    for node in graph.node_indices() {
+        let position = graph::world_position(&graph[node]);
+        shapes.circle(position, NODE_RADIUS, NODE_COLOR);
+        shapes.label(position, node.index().to_string(), NODE_COLOR);
+    }
+    for edge in graph.edge_indices() {
+        let Some((start, end)) = graph.edge_endpoints(edge) else { continue };
+        let (start, end) = (graph::world_position(&graph[start]), graph::world_position(&graph[end]));
+        let length = start.distance(end);
+        let color = match graph[edge].kind {
+            EdgeKind::Road => EDGE_COLOR,
+            EdgeKind::Footpath => FOOTPATH_EDGE_COLOR,
+        };
+        let Some(direction) = (end - start).try_normalize() else {
+            // nowhere to point; mark it so it isn't missed
+            shapes.cross(start, NODE_RADIUS * 2., color);
+            continue;
+        };
+        let right = Vec2::new(direction.y, -direction.x);
+        let from = start + direction * NODE_RADIUS + right * EDGE_OFFSET;
+        let to = end - direction * NODE_RADIUS + right * EDGE_OFFSET;
+        shapes.line(from, to, color);
+        for side in [ARROW_ANGLE, -ARROW_ANGLE] {
+            let back = Vec2::from_angle(side).rotate(-direction);
+            shapes.line(to, to + back * ARROW_LENGTH, color);
+        }
+        shapes.label(from.lerp(to, 0.5) + right * LABEL_OFFSET, format!("{:.0}", length), color);
+    }
+}
+
+/// The road graph, colliders, the path being drawn, how far each paperboy can throw and the
+/// dogs' territories; or just the graph in the graph view
+pub fn draw_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    world: Res<GameWorld>,
+    mut shapes: ResMut<DebugShapes>,
+    colliders: Query<&Transform, With<crate::Collider>>,
+    paths: Query<&crate::Path>,
+    paperboys: Query<&Transform, With<crate::Paperboy>>,
+    dogs: Query<&crate::dogs::Dog>,
+) {
+    if overlay.enabled || overlay.graph {
+        draw_graph(&world, &mut shapes);
+    }
+    if !overlay.enabled {
+        return;
+    }
+    for transform in &colliders {
+        shapes.rect(transform.translation.truncate(), transform.scale.truncate(), transform.rotation, COLLIDER_COLOR);
+    }
+    for path in &paths {
+        for point in &path.points {
+            shapes.cross(*point, PATH_POINT_SIZE, PATH_POINT_COLOR);
+        }
+    }
+    for transform in &paperboys {
+        shapes.circle(transform.translation.truncate(), crate::PAPERBOY_REACH, REACH_COLOR);
+    }
+    for dog in &dogs {
+        shapes.circle(dog.kennel, dog.territory, TERRITORY_COLOR);
+    }
+}
+
+type DebugLineParts<'a> = (&'a mut Transform, &'a mut Sprite, &'a mut Visibility);
+type DebugLabelParts<'a> = (&'a mut Transform, &'a mut Text, &'a mut Visibility);
+
+/// Draws and empties `DebugShapes`, reusing sprites and texts from earlier frames and hiding
+/// the ones left over
+pub fn render_debug_shapes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut shapes: ResMut<DebugShapes>,
+    mut lines: Query<DebugLineParts, (With<DebugLine>, Without<DebugLabel>)>,
+    mut labels: Query<DebugLabelParts, (With<DebugLabel>, Without<DebugLine>)>,
+) {
+    let line_transform = |from: Vec2, to: Vec2| {
+        let along = to - from;
+        Transform {
+            translation: from.lerp(to, 0.5).extend(DEBUG_Z),
+            rotation: Quat::from_rotation_z(along.y.atan2(along.x)),
+            scale: Vec3::new(along.length(), DEBUG_LINE_THICKNESS, 1.0),
+        }
+    };
+
+    let mut pooled = lines.iter_mut();
+    for (from, to, color) in shapes.lines.drain(..) {
+        if let Some((mut transform, mut sprite, mut visibility)) = pooled.next() {
+            *transform = line_transform(from, to);
+            sprite.color = color;
+            *visibility = Visibility::Inherited;
+        } else {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite { color, ..default() },
+                    transform: line_transform(from, to),
+                    ..default()
+                },
+                DebugLine,
+            ));
+        }
+    }
+    for (_, _, mut visibility) in pooled {
+        *visibility = Visibility::Hidden;
+    }
+
+    let mut pooled = labels.iter_mut();
+    for (position, value, color) in shapes.labels.drain(..) {
+        if let Some((mut transform, mut text, mut visibility)) = pooled.next() {
+            transform.translation = position.extend(DEBUG_Z + 0.1);
+            text.sections[0].value = value;
+            text.sections[0].style.color = color;
+            *visibility = Visibility::Inherited;
+        } else {
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        value,
+                        TextStyle {
+                            font: asset_server.load(crate::FONT_PATH),
+                            font_size: DEBUG_FONT_SIZE,
+                            color,
+                        },
+                    ).with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(position.extend(DEBUG_Z + 0.1)),
+                    ..default()
+                },
+                DebugLabel,
+            ));
+        }
+    }
+    for (_, _, mut visibility) in pooled {
+        *visibility = Visibility::Hidden;
+    }
+}
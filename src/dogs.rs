@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::economy::Bag;
+use crate::graph::{self, GameWorld};
+use crate::spatial::{Shape, SpatialIndex};
+use crate::vehicle::{Vehicle, VehicleKind};
+use crate::{models, routing};
+
+// world units per second; more than a walker, less than a bicycle
+const DOG_SPEED: f32 = 80.0;
+const DOG_SIZE: Vec2 = Vec2::new(12., 8.);
+const DOG_COLOR: Color = Color::rgb(0.45, 0.3, 0.15);
+// seconds between a chasing dog working out the way to its target again
+const REPLAN_INTERVAL: f32 = 0.5;
+// closer than this a dog runs straight at what it's after
+const DIRECT_DISTANCE: f32 = 40.0;
+const CATCH_DISTANCE: f32 = 12.0;
+// papers a caught paperboy loses
+const DROPPED_PAPERS: u32 = 3;
+// seconds after a catch before a dog will chase anyone again
+const CALM_DOWN: f32 = 5.0;
+
+/// A dog kept at one of the map's houses, as given by a level
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DogSpec {
+    // index into the map's houses
+    pub house: usize,
+    // world units around its house it will chase paperboys
+    pub territory: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DogState {
+    // at home, or on the way back with nobody to chase
+    Home,
+    Chasing(Entity),
+    // after catching someone, going back home and ignoring paperboys for a while
+    Returning,
+}
+
+#[derive(Component, Debug)]
+pub struct Dog {
+    // where it lives, on the porch of its house
+    pub kennel: Vec2,
+    pub territory: f32,
+    pub state: DogState,
+    waypoints: Vec<Vec2>,
+    replan: Timer,
+    // seconds left until it calms down from its last catch
+    calm_down: f32,
+}
+
+/// How dogs get around, for routing them along the roads
+fn dog_vehicle() -> Vehicle {
+    Vehicle { max_speed: DOG_SPEED, ..Vehicle::new(VehicleKind::Walker) }
+}
+
+/// Spawns the dogs in `specs` at their houses, out of the map's `houses`
+pub fn spawn_dogs(commands: &mut Commands, specs: &[DogSpec], houses: &[Vec2]) {
+    for spec in specs {
+        let Some(house) = houses.get(spec.house) else {
+            warn!("a dog lives at house {}, but the map only has {}", spec.house, houses.len());
+            continue;
+        };
+        let kennel = models::porch_position(*house);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: DOG_COLOR,
+                    ..default()
+                },
+                transform: Transform {
+                    // over the roads and paperboys
+                    translation: kennel.extend(0.2),
+                    scale: DOG_SIZE.extend(1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            Dog {
+                kennel,
+                territory: spec.territory,
+                state: DogState::Home,
+                waypoints: vec![],
+                replan: Timer::from_seconds(REPLAN_INTERVAL, TimerMode::Repeating),
+                calm_down: 0.,
+            },
+        ));
+    }
+}
+
+/// Marks the edges running through any dog's territory, so routes keep out of it where they can
+pub fn mark_dog_territory(mut world: ResMut<GameWorld>, dogs: Query<&Dog>) {
+    let guarded = |start: Vec2, end: Vec2| dogs.iter().any(|dog| Shape::Segment(start, end).distance(dog.kennel) < dog.territory);
+    // only touch the world when something changes, as everything routing off it rebuilds then
+    let changed: Vec<_> = world.graph.edge_indices()
+        .filter_map(|edge| {
+            let (start, end) = world.graph.edge_endpoints(edge)?;
+            let is_guarded = guarded(graph::world_position(&world.graph[start]), graph::world_position(&world.graph[end]));
+            (world.graph[edge].guarded != is_guarded).then_some((edge, is_guarded))
+        })
+        .collect();
+    for (edge, guarded) in changed {
+        world.graph[edge].guarded = guarded;
+    }
+}
+
+/// Dogs go after the nearest paperboy in their territory, along the roads until they're close,
+/// but never out of it. A paperboy they catch drops papers, and the dog goes home to calm down.
+pub fn chase_paperboys(
+    time: Res<Time>,
+    world: Res<GameWorld>,
+    index: Res<SpatialIndex>,
+    mut dogs: Query<(&mut Dog, &mut Transform), Without<crate::Paperboy>>,
+    mut paperboys: Query<(&Transform, &mut Bag), With<crate::Paperboy>>,
+) {
+    let vehicle = dog_vehicle();
+    for (mut dog, mut transform) in &mut dogs {
+        let position = transform.translation.truncate();
+        dog.calm_down = (dog.calm_down - time.delta_seconds()).max(0.);
+        if dog.state != DogState::Returning {
+            let target = index.paperboys.within(dog.kennel, dog.territory).first().map(|(paperboy, _)| *paperboy);
+            match (target, dog.state) {
+                (Some(paperboy), state) if state != DogState::Chasing(paperboy) => {
+                    debug!("a dog is chasing paperboy {:?}", paperboy);
+                    dog.state = DogState::Chasing(paperboy);
+                    dog.waypoints.clear();
+                }
+                (None, DogState::Chasing(_)) => {
+                    dog.state = DogState::Home;
+                    dog.waypoints.clear();
+                }
+                _ => {}
+            }
+        }
+
+        let goal = match dog.state {
+            DogState::Home if position.distance(dog.kennel) < 1. => continue,
+            DogState::Home | DogState::Returning => dog.kennel,
+            DogState::Chasing(paperboy) => match paperboys.get(paperboy) {
+                Ok((paperboy_transform, _)) => paperboy_transform.translation.truncate(),
+                Err(_) => {
+                    dog.state = DogState::Home;
+                    continue;
+                }
+            },
+        };
+        let replan = dog.replan.tick(time.delta()).just_finished();
+        if position.distance(goal) < DIRECT_DISTANCE {
+            dog.waypoints = vec![goal];
+        } else if dog.waypoints.is_empty() || replan {
+            dog.waypoints = routing::route_to(&world, &index.roads, position, goal, &vehicle).unwrap_or_else(|| vec![goal]);
+        }
+
+        // run along the waypoints, as far as this frame allows
+        let mut budget = DOG_SPEED * time.delta_seconds();
+        let mut next = position;
+        while let Some(target) = dog.waypoints.first().copied() {
+            let distance = next.distance(target);
+            if distance > budget {
+                next += (target - next) / distance * budget;
+                break;
+            }
+            next = target;
+            budget -= distance;
+            dog.waypoints.remove(0);
+        }
+        let from_kennel = next - dog.kennel;
+        if from_kennel.length() > dog.territory {
+            next = dog.kennel + from_kennel.normalize() * dog.territory;
+        }
+        transform.translation = next.extend(transform.translation.z);
+
+        match dog.state {
+            DogState::Chasing(paperboy) if next.distance(goal) < CATCH_DISTANCE => {
+                if let Ok((_, mut bag)) = paperboys.get_mut(paperboy) {
+                    let dropped = bag.papers.min(DROPPED_PAPERS);
+                    bag.papers -= dropped;
+                    info!("a dog caught paperboy {:?}, who dropped {} papers", paperboy, dropped);
+                }
+                dog.state = DogState::Returning;
+                dog.waypoints.clear();
+                dog.calm_down = CALM_DOWN;
+            }
+            DogState::Returning if next.distance(dog.kennel) < 1. && dog.calm_down <= 0. => dog.state = DogState::Home,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::path::Path;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{Action, ActionState};
+use crate::models;
+use crate::scoring::Score;
+use crate::shift::{Shift, ShiftEnded, StartShift};
+use crate::storage;
+use crate::vehicle::{Vehicle, VehicleKind};
+
+pub const PROFILE_PATH: &str = "saves/profile.ron";
+
+// dollars paid for every paper delivered
+pub const WAGE: u32 = 2;
+// dollars tipped for a paper delivered the moment it was ordered, less the closer it gets to due
+const MAX_TIP: f32 = 5.0;
+
+pub const BASE_BAG_CAPACITY: u32 = 5;
+// extra papers per bag upgrade
+const BAG_UPGRADE_CAPACITY: u32 = 3;
+const MAX_HIRED: u32 = 3;
+
+const BICYCLE_PRICE: u32 = 60;
+const SCOOTER_PRICE: u32 = 150;
+// prices go up with every upgrade already bought
+const BAG_PRICE: u32 = 25;
+const HIRE_PRICE: u32 = 100;
+
+// how close to the depot a paperboy has to be to refill their bag
+const DEPOT_RADIUS: f32 = 30.0;
+const DEPOT_SIZE: Vec2 = Vec2::new(30., 30.);
+const DEPOT_COLOR: Color = Color::rgb(0.4, 0.7, 0.4);
+
+const SHOP_FONT_SIZE: f32 = 20.0;
+const SHOP_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+const SHOP_SELECTED_COLOR: Color = Color::rgb(0.2, 0.2, 1.0);
+const SHOP_BACKGROUND_COLOR: Color = Color::rgba(1., 1., 1., 0.9);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Upgrades {
+    pub bicycle: bool,
+    // profiles from before scooters were sold don't have one
+    #[serde(default)]
+    pub scooter: bool,
+    // bag upgrades bought
+    pub bag: u32,
+    // paperboys hired on top of the ones a level starts with
+    pub hired: u32,
+}
+
+/// The player's money and upgrades, kept between runs
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub money: u32,
+    pub upgrades: Upgrades,
+}
+
+impl Profile {
+    /// Loads the profile at `path`, falling back on its backup if the file is corrupted, so
+    /// the next save can't wipe out the player's money and upgrades
+    pub fn load(path: &Path) -> Profile {
+        storage::load(path, "profile")
+    }
+
+    /// Writes the profile safely, keeping the previous one as a backup
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::save(self, path)
+    }
+
+    /// The best vehicle bought so far, which every paperboy uses
+    pub fn vehicle(&self) -> VehicleKind {
+        if self.upgrades.scooter {
+            VehicleKind::Scooter
+        } else if self.upgrades.bicycle {
+            VehicleKind::Bicycle
+        } else {
+            VehicleKind::Walker
+        }
+    }
+
+    pub fn bag_capacity(&self) -> u32 {
+        BASE_BAG_CAPACITY + self.upgrades.bag * BAG_UPGRADE_CAPACITY
+    }
+}
+
+/// Tip for a paper landing at `now`, by how much of its time the order had left
+pub fn tip(order: &models::Order, now: f32) -> u32 {
+    let left = ((order.deadline - now) / models::ORDER_DEADLINE).clamp(0., 1.);
+    (MAX_TIP * left).round() as u32
+}
+
+/// Papers a paperboy is carrying
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Bag {
+    pub papers: u32,
+    pub capacity: u32,
+}
+
+/// Where paperboys pick up papers and rest, at the level's spawn point
+#[derive(Component)]
+pub struct Depot;
+
+pub fn spawn_depot(commands: &mut Commands, position: Vec2) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: DEPOT_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                // under the paperboys standing on it
+                translation: position.extend(-0.5),
+                scale: DEPOT_SIZE.extend(1.0),
+                ..default()
+            },
+            ..default()
+        },
+        Depot,
+        crate::stamina::RestSpot,
+    )).id()
+}
+
+/// Gives new paperboys a full bag
+pub fn equip_bags(
+    mut commands: Commands,
+    profile: Res<Profile>,
+    paperboys: Query<Entity, (With<crate::Paperboy>, Without<Bag>)>,
+) {
+    for paperboy in &paperboys {
+        let capacity = profile.bag_capacity();
+        commands.entity(paperboy).insert(Bag { papers: capacity, capacity });
+    }
+}
+
+/// Tops up the bags of paperboys standing at the depot
+pub fn refill_bags(
+    depots: Query<&Transform, With<Depot>>,
+    mut paperboys: Query<(&Transform, &mut Bag), With<crate::Paperboy>>,
+) {
+    for (transform, mut bag) in &mut paperboys {
+        let position = transform.translation.truncate();
+        if bag.papers < bag.capacity
+            && depots.iter().any(|depot| depot.translation.truncate().distance(position) < DEPOT_RADIUS)
+        {
+            bag.papers = bag.capacity;
+        }
+    }
+}
+
+/// Every shift starts with full bags
+pub fn restock_bags(mut start_shift: EventReader<StartShift>, mut bags: Query<&mut Bag>) {
+    if start_shift.iter().count() == 0 {
+        return;
+    }
+    for mut bag in &mut bags {
+        bag.papers = bag.capacity;
+    }
+}
+
+/// Pays out the shift's wages and tips into the profile
+pub fn bank_earnings(
+    mut shift_ended: EventReader<ShiftEnded>,
+    score: Res<Score>,
+    mut profile: ResMut<Profile>,
+) {
+    for _ in shift_ended.iter() {
+        profile.money += score.wages + score.tips;
+        info!("earned ${} in wages and ${} in tips, ${} in the bank", score.wages, score.tips, profile.money);
+        if let Err(e) = profile.save(Path::new(PROFILE_PATH)) {
+            error!("could not save profile to {:?}: {}", PROFILE_PATH, e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShopItem {
+    Bicycle,
+    Scooter,
+    Bag,
+    Hire,
+}
+
+impl ShopItem {
+    const ALL: [ShopItem; 4] = [ShopItem::Bicycle, ShopItem::Scooter, ShopItem::Bag, ShopItem::Hire];
+
+    /// Price of the next one, or None if it can't be bought any more
+    fn price(&self, upgrades: &Upgrades) -> Option<u32> {
+        match self {
+            ShopItem::Bicycle => (!upgrades.bicycle && !upgrades.scooter).then_some(BICYCLE_PRICE),
+            ShopItem::Scooter => (!upgrades.scooter).then_some(SCOOTER_PRICE),
+            ShopItem::Bag => Some(BAG_PRICE * (upgrades.bag + 1)),
+            ShopItem::Hire => (upgrades.hired < MAX_HIRED).then_some(HIRE_PRICE * (upgrades.hired + 1)),
+        }
+    }
+
+    fn describe(&self, profile: &Profile) -> String {
+        match self {
+            ShopItem::Bicycle => "Bicycle - faster, but slow to corner, stays off footpaths and throws less accurately".to_string(),
+            ShopItem::Scooter => "Scooter - fastest on a straight road, slow to get going and to corner".to_string(),
+            ShopItem::Bag => format!("Bigger bag - {} papers instead of {}", profile.bag_capacity() + BAG_UPGRADE_CAPACITY, profile.bag_capacity()),
+            ShopItem::Hire => format!("Hire a paperboy - {} hired so far", profile.upgrades.hired),
+        }
+    }
+}
+
+/// Upgrades can only be bought between shifts
+#[derive(Resource, Default)]
+pub struct ShopScreen {
+    pub open: bool,
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct ShopText;
+
+pub fn setup_shop(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: SHOP_FONT_SIZE,
+                color: SHOP_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(80.0),
+                right: Val::Px(80.0),
+                ..default()
+            },
+            ..default()
+        }).with_background_color(SHOP_BACKGROUND_COLOR),
+        ShopText,
+    ));
+}
+
+/// What the upgrades on sale change: the paperboys, their bags, and the depot new hires start at
+#[derive(SystemParam)]
+pub struct Crew<'w, 's> {
+    bags: Query<'w, 's, &'static mut Bag>,
+    paperboys: Query<'w, 's, Entity, With<crate::Paperboy>>,
+    depots: Query<'w, 's, &'static Transform, With<Depot>>,
+}
+
+/// The shop overlay: which item is picked, and the text listing them
+#[derive(SystemParam)]
+pub struct ShopOverlay<'w, 's> {
+    screen: ResMut<'w, ShopScreen>,
+    text: Query<'w, 's, (&'static mut Text, &'static mut Visibility), With<ShopText>>,
+}
+
+pub fn shop_screen(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    keys: Res<Input<KeyCode>>,
+    shift: Res<Shift>,
+    mut profile: ResMut<Profile>,
+    overlay: ShopOverlay,
+    crew: Crew,
+) {
+    let ShopOverlay { mut screen, mut text } = overlay;
+    let Crew { mut bags, paperboys, depots } = crew;
+    if actions.just_pressed(Action::Shop) {
+        screen.open = !screen.open && shift.is_over();
+    }
+    if !shift.is_over() {
+        screen.open = false;
+    }
+
+    if screen.open {
+        let count = ShopItem::ALL.len();
+        if keys.just_pressed(KeyCode::Up) {
+            screen.selected = (screen.selected + count - 1) % count;
+        } else if keys.just_pressed(KeyCode::Down) {
+            screen.selected = (screen.selected + 1) % count;
+        } else if keys.just_pressed(KeyCode::Return) {
+            let item = ShopItem::ALL[screen.selected];
+            match item.price(&profile.upgrades) {
+                Some(price) if price <= profile.money => {
+                    profile.money -= price;
+                    match item {
+                        ShopItem::Bicycle | ShopItem::Scooter => {
+                            if item == ShopItem::Bicycle {
+                                profile.upgrades.bicycle = true;
+                            } else {
+                                profile.upgrades.scooter = true;
+                            }
+                            // picked up again with the new vehicle
+                            for paperboy in &paperboys {
+                                commands.entity(paperboy).remove::<Vehicle>();
+                            }
+                        }
+                        ShopItem::Bag => {
+                            profile.upgrades.bag += 1;
+                            for mut bag in &mut bags {
+                                bag.capacity = profile.bag_capacity();
+                                bag.papers = bag.capacity;
+                            }
+                        }
+                        ShopItem::Hire => {
+                            profile.upgrades.hired += 1;
+                            // starts work at the depot straight away
+                            let position = depots.iter().next().map_or(Vec2::ZERO, |depot| depot.translation.truncate());
+                            crate::spawn_paperboy(&mut commands, position);
+                        }
+                    }
+                    info!("bought {:?} for ${}, ${} left", item, price, profile.money);
+                    if let Err(e) = profile.save(Path::new(PROFILE_PATH)) {
+                        error!("could not save profile to {:?}: {}", PROFILE_PATH, e);
+                    }
+                }
+                Some(price) => info!("{:?} costs ${}, only ${} in the bank", item, price, profile.money),
+                None => info!("{:?} can't be bought any more", item),
+            }
+        }
+    }
+
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else { return };
+    if !screen.open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let style = text.sections[0].style.clone();
+    let mut sections = vec![TextSection::new(
+        format!("Shop - ${} in the bank. Up/Down to select, Enter to buy\n", profile.money),
+        style.clone(),
+    )];
+    for (index, item) in ShopItem::ALL.iter().enumerate() {
+        let price = match item.price(&profile.upgrades) {
+            Some(price) => format!("${}", price),
+            None => "sold out".to_string(),
+        };
+        sections.push(TextSection::new(
+            format!("{}: {}\n", price, item.describe(&profile)),
+            TextStyle {
+                color: if index == screen.selected { SHOP_SELECTED_COLOR } else { style.color },
+                ..style.clone()
+            },
+        ));
+    }
+    text.sections = sections;
+}
@@ -2,9 +2,10 @@ use bevy::math::Vec2;
 use bevy::utils::petgraph::Graph;
 use bevy::math::*;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Component, Debug, Clone)]
-pub(crate) struct RoadNode {
+pub struct RoadNode {
     pub pos: Vec2
 }
 
@@ -21,13 +22,72 @@ impl Default for RoadNode {
     fn default() -> RoadNode { RoadNode::from_xy(0., 0.) }
 }
 
-#[derive(Resource, Clone)]
-pub(crate) struct GameWorld {
-    pub graph: Graph::<RoadNode, i32>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeKind {
+    #[default]
+    Road,
+    // only usable on foot
+    Footpath,
 }
 
-pub(crate) fn create_graph() -> GameWorld {
-    let mut deps = Graph::<RoadNode, i32>::new();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoadEdge {
+    pub kind: EdgeKind,
+    // shut for now by one of the level's closures; nobody can use it until it reopens
+    pub closed: bool,
+    // runs through a dog's territory
+    pub guarded: bool,
+}
+
+#[derive(Resource, Clone, Default)]
+pub struct GameWorld {
+    pub graph: Graph::<RoadNode, RoadEdge>
+}
+
+/// Plain node positions and edges as node indices, used to store a `GameWorld` on disk
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraphData {
+    pub nodes: Vec<Vec2>,
+    pub edges: Vec<(usize, usize)>,
+    // maps from before footpaths don't have any
+    #[serde(default)]
+    pub footpaths: Vec<(usize, usize)>,
+}
+
+impl From<&GameWorld> for GraphData {
+    fn from(world: &GameWorld) -> GraphData {
+        let edges_of_kind = |kind: EdgeKind| world.graph.edge_indices()
+            .filter(|edge| world.graph[*edge].kind == kind)
+            .filter_map(|edge| world.graph.edge_endpoints(edge))
+            .map(|(start, end)| (start.index(), end.index()))
+            .collect();
+        GraphData {
+            nodes: world.graph.node_weights().map(|node| node.pos).collect(),
+            edges: edges_of_kind(EdgeKind::Road),
+            footpaths: edges_of_kind(EdgeKind::Footpath),
+        }
+    }
+}
+
+impl From<&GraphData> for GameWorld {
+    fn from(data: &GraphData) -> GameWorld {
+        let mut graph = Graph::<RoadNode, RoadEdge>::new();
+        let nodes: Vec<_> = data.nodes.iter().map(|pos| graph.add_node(RoadNode::from_xy(pos.x, pos.y))).collect();
+        let footpath = RoadEdge { kind: EdgeKind::Footpath, ..default() };
+        graph.extend_with_edges(data.edges.iter().map(|(start, end)| (nodes[*start], nodes[*end])));
+        graph.extend_with_edges(data.footpaths.iter().map(|(start, end)| (nodes[*start], nodes[*end], footpath)));
+        GameWorld { graph }
+    }
+}
+
+/// Where a node's graph coordinates end up in the world, matching how `spawn_roads` lays roads out
+pub fn world_position(node: &RoadNode) -> Vec2 {
+    let offset_x = (crate::RIGHT_WALL - crate::LEFT_WALL) / 4.;
+    Vec2::new(node.pos.x * crate::SCALEUP_FACTOR - offset_x, node.pos.y * crate::SCALEUP_FACTOR)
+}
+
+pub fn create_graph() -> GameWorld {
+    let mut deps = Graph::<RoadNode, RoadEdge>::new();
 
     // test graph:
     // A--------G
@@ -68,7 +128,7 @@ pub(crate) fn create_graph() -> GameWorld {
       (i, f),
     ]);
 
-    println!("{:?}", deps);
+    trace!("{:?}", deps);
     return GameWorld {
         graph: deps
     }
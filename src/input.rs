@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const BINDINGS_PATH: &str = "settings/bindings.ron";
+
+const REBIND_FONT_SIZE: f32 = 20.0;
+const REBIND_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+const REBIND_SELECTED_COLOR: Color = Color::rgb(0.2, 0.2, 1.0);
+const REBIND_BACKGROUND_COLOR: Color = Color::rgba(1., 1., 1., 0.9);
+
+/// Everything the player can do, independent of which key or button does it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Place,
+    Deliver,
+    Dispatch,
+    ToggleAutoThrow,
+    CycleMode,
+    ClearPath,
+    Undo,
+    Pause,
+    ToggleFollow,
+    DragCamera,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    QuickSave,
+    QuickLoad,
+    Leaderboard,
+    LevelSelect,
+    NextPaperboy,
+    Shop,
+    PlanRoute,
+    Rebind,
+    DebugOverlay,
+    GraphView,
+}
+
+impl Action {
+    pub const ALL: [Action; 24] = [
+        Action::Place,
+        Action::Deliver,
+        Action::Dispatch,
+        Action::ToggleAutoThrow,
+        Action::CycleMode,
+        Action::ClearPath,
+        Action::Undo,
+        Action::Pause,
+        Action::ToggleFollow,
+        Action::DragCamera,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::PanUp,
+        Action::PanDown,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::Leaderboard,
+        Action::LevelSelect,
+        Action::NextPaperboy,
+        Action::Shop,
+        Action::PlanRoute,
+        Action::Rebind,
+        Action::DebugOverlay,
+        Action::GraphView,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    // any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        use Binding::*;
+        KeyBindings(BTreeMap::from([
+            (Action::Place, vec![Mouse(MouseButton::Left)]),
+            (Action::Deliver, vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::South)]),
+            (Action::Dispatch, vec![Key(KeyCode::Return), Gamepad(GamepadButtonType::RightTrigger)]),
+            (Action::ToggleAutoThrow, vec![Key(KeyCode::T)]),
+            (Action::CycleMode, vec![Key(KeyCode::Tab), Gamepad(GamepadButtonType::North)]),
+            (Action::ClearPath, vec![Key(KeyCode::Q), Gamepad(GamepadButtonType::West)]),
+            (Action::Undo, vec![Mouse(MouseButton::Right), Key(KeyCode::Z), Gamepad(GamepadButtonType::East)]),
+            (Action::Pause, vec![Key(KeyCode::P), Gamepad(GamepadButtonType::Start)]),
+            (Action::ToggleFollow, vec![Key(KeyCode::F), Gamepad(GamepadButtonType::Select)]),
+            (Action::DragCamera, vec![Mouse(MouseButton::Middle)]),
+            (Action::PanLeft, vec![Key(KeyCode::Left), Gamepad(GamepadButtonType::DPadLeft)]),
+            (Action::PanRight, vec![Key(KeyCode::Right), Gamepad(GamepadButtonType::DPadRight)]),
+            (Action::PanUp, vec![Key(KeyCode::Up), Gamepad(GamepadButtonType::DPadUp)]),
+            (Action::PanDown, vec![Key(KeyCode::Down), Gamepad(GamepadButtonType::DPadDown)]),
+            (Action::QuickSave, vec![Key(KeyCode::F5)]),
+            (Action::QuickLoad, vec![Key(KeyCode::F9)]),
+            (Action::Leaderboard, vec![Key(KeyCode::L)]),
+            (Action::LevelSelect, vec![Key(KeyCode::M)]),
+            (Action::Shop, vec![Key(KeyCode::B)]),
+            (Action::PlanRoute, vec![Key(KeyCode::R)]),
+            (Action::NextPaperboy, vec![Key(KeyCode::E), Gamepad(GamepadButtonType::RightTrigger2)]),
+            (Action::Rebind, vec![Key(KeyCode::F1)]),
+            (Action::DebugOverlay, vec![Key(KeyCode::F3)]),
+            (Action::GraphView, vec![Key(KeyCode::F4)]),
+        ]))
+    }
+}
+
+impl KeyBindings {
+    /// Reads bindings from `path`, falling back to the defaults if the file is missing or broken.
+    /// Actions missing from the file keep their default bindings.
+    pub fn load(path: &Path) -> KeyBindings {
+        let mut bindings = KeyBindings::default();
+        match fs::read_to_string(path) {
+            Ok(contents) => match ron::from_str::<KeyBindings>(&contents) {
+                Ok(loaded) => bindings.0.extend(loaded.0),
+                Err(e) => warn!("could not parse {:?}, using default bindings: {}", path, e),
+            },
+            Err(_) => {
+                info!("no bindings at {:?}, writing defaults", path);
+                bindings.save(path);
+            }
+        }
+        bindings
+    }
+
+    pub fn save(&self, path: &Path) {
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(e) => {
+                error!("could not serialize bindings: {}", e);
+                return;
+            }
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(e) = fs::write(path, contents) {
+            error!("could not write bindings to {:?}: {}", path, e);
+        }
+    }
+
+    fn bindings(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// Which actions are held, or were pressed this frame, after resolving bindings
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    fn clear(&mut self) {
+        self.pressed.clear();
+        self.just_pressed.clear();
+    }
+}
+
+/// Every device an action can be bound to
+#[derive(SystemParam)]
+pub struct Devices<'w> {
+    keys: Res<'w, Input<KeyCode>>,
+    mouse: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+pub fn update_action_state(
+    bindings: Res<KeyBindings>,
+    rebinding: Res<RebindingScreen>,
+    level_select: Res<crate::levels::LevelSelectScreen>,
+    shop: Res<crate::economy::ShopScreen>,
+    devices: Devices,
+    mut state: ResMut<ActionState>,
+) {
+    let Devices { keys, mouse, gamepads, gamepad_buttons } = devices;
+    state.clear();
+
+    for action in Action::ALL {
+        // the rebinding screen swallows all input except the key that closes it
+        if rebinding.open && (action != Action::Rebind || rebinding.waiting.is_some()) {
+            continue;
+        }
+        // and so does the level select screen, which uses the arrow keys and enter itself
+        if level_select.open && action != Action::LevelSelect {
+            continue;
+        }
+        if shop.open && action != Action::Shop {
+            continue;
+        }
+        for binding in bindings.bindings(action) {
+            let (pressed, just_pressed) = match *binding {
+                Binding::Key(key) => (keys.pressed(key), keys.just_pressed(key)),
+                Binding::Mouse(button) => (mouse.pressed(button), mouse.just_pressed(button)),
+                Binding::Gamepad(button_type) => gamepads.iter()
+                    .map(|gamepad| GamepadButton::new(gamepad, button_type))
+                    .fold((false, false), |(pressed, just_pressed), button| {
+                        (pressed || gamepad_buttons.pressed(button), just_pressed || gamepad_buttons.just_pressed(button))
+                    }),
+            };
+            if pressed {
+                state.pressed.insert(action);
+            }
+            if just_pressed {
+                state.just_pressed.insert(action);
+            }
+        }
+    }
+}
+
+/// State of the overlay that lists actions and lets the player bind them to new inputs
+#[derive(Resource, Default)]
+pub struct RebindingScreen {
+    pub open: bool,
+    selected: usize,
+    // action that will be bound to the next key or button pressed
+    waiting: Option<Action>,
+}
+
+#[derive(Component)]
+pub struct RebindingText;
+
+pub fn setup_rebinding_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: REBIND_FONT_SIZE,
+                color: REBIND_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(40.0),
+                right: Val::Px(40.0),
+                ..default()
+            },
+            ..default()
+        }).with_background_color(REBIND_BACKGROUND_COLOR),
+        RebindingText,
+    ));
+}
+
+fn describe(bindings: &[Binding]) -> String {
+    if bindings.is_empty() {
+        return "(unbound)".to_string();
+    }
+    bindings.iter().map(|binding| match binding {
+        Binding::Key(key) => format!("{:?}", key),
+        Binding::Mouse(button) => format!("Mouse {:?}", button),
+        Binding::Gamepad(button) => format!("Pad {:?}", button),
+    }).collect::<Vec<_>>().join(", ")
+}
+
+pub fn rebinding_screen(
+    actions: Res<ActionState>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut screen: ResMut<RebindingScreen>,
+    mut bindings: ResMut<KeyBindings>,
+    mut text: Query<(&mut Text, &mut Visibility), With<RebindingText>>,
+) {
+    if actions.just_pressed(Action::Rebind) {
+        screen.open = !screen.open;
+        screen.waiting = None;
+    }
+
+    if screen.open {
+        if let Some(action) = screen.waiting {
+            // escape closes the game, so it can't be captured as a binding
+            let captured = keys.get_just_pressed().find(|key| **key != KeyCode::Escape).map(|key| Binding::Key(*key))
+                .or_else(|| mouse.get_just_pressed().next().map(|button| Binding::Mouse(*button)))
+                .or_else(|| gamepad_buttons.get_just_pressed().next().map(|button| Binding::Gamepad(button.button_type)));
+            if let Some(binding) = captured {
+                info!("binding {:?} to {:?}", action, binding);
+                bindings.0.insert(action, vec![binding]);
+                bindings.save(Path::new(BINDINGS_PATH));
+                screen.waiting = None;
+            }
+        } else if keys.just_pressed(KeyCode::Up) {
+            screen.selected = (screen.selected + Action::ALL.len() - 1) % Action::ALL.len();
+        } else if keys.just_pressed(KeyCode::Down) {
+            screen.selected = (screen.selected + 1) % Action::ALL.len();
+        } else if keys.just_pressed(KeyCode::Return) {
+            screen.waiting = Some(Action::ALL[screen.selected]);
+        } else if keys.just_pressed(KeyCode::Back) {
+            *bindings = KeyBindings::default();
+            bindings.save(Path::new(BINDINGS_PATH));
+        }
+    }
+
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else { return };
+    if !screen.open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let style = text.sections[0].style.clone();
+    let mut sections = vec![TextSection::new(
+        "Key bindings - Up/Down to select, Enter to rebind, Backspace to reset\n",
+        style.clone(),
+    )];
+    for (index, action) in Action::ALL.iter().enumerate() {
+        let value = if screen.waiting == Some(*action) {
+            format!("{:?}: press a key or button...\n", action)
+        } else {
+            format!("{:?}: {}\n", action, describe(bindings.bindings(*action)))
+        };
+        let color = if index == screen.selected { REBIND_SELECTED_COLOR } else { style.color };
+        sections.push(TextSection::new(value, TextStyle { color, ..style.clone() }));
+    }
+    text.sections = sections;
+}
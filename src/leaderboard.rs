@@ -0,0 +1,216 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph;
+use crate::input::{Action, ActionState};
+use crate::scoring::Score;
+use crate::shift::{Shift, ShiftEnded, StartShift};
+use crate::storage;
+
+pub const LEADERBOARD_PATH: &str = "saves/leaderboard.ron";
+
+// entries shown for the current map and seed
+const SHOWN_ENTRIES: usize = 10;
+
+const LEADERBOARD_FONT_SIZE: f32 = 20.0;
+const LEADERBOARD_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+const LEADERBOARD_BACKGROUND_COLOR: Color = Color::rgba(1., 1., 1., 0.9);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub map: String,
+    pub seed: u32,
+    pub score: i32,
+    pub deliveries: u32,
+    pub late_deliveries: u32,
+    // total seconds orders were delivered past their deadline
+    pub lateness: f32,
+    // seconds since the unix epoch
+    pub date: u64,
+}
+
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+/// Identifies a map by its layout, so an edited map gets a table of its own. Uses FNV-1a
+/// rather than std's hasher, whose output may change between Rust releases.
+pub fn map_id(map: &graph::GameWorld) -> String {
+    let data = graph::GraphData::from(map);
+    let mut bytes = vec![];
+    for node in &data.nodes {
+        bytes.extend(node.x.to_le_bytes());
+        bytes.extend(node.y.to_le_bytes());
+    }
+    for (start, end) in &data.edges {
+        bytes.extend((*start as u64).to_le_bytes());
+        bytes.extend((*end as u64).to_le_bytes());
+    }
+    // only hashed when there are any, so maps from before footpaths keep their tables
+    if !data.footpaths.is_empty() {
+        bytes.extend(b"footpaths");
+        for (start, end) in &data.footpaths {
+            bytes.extend((*start as u64).to_le_bytes());
+            bytes.extend((*end as u64).to_le_bytes());
+        }
+    }
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Scores are only comparable within a level, which fixes the order rate and paperboy count
+pub fn level_map_id(level: &crate::levels::Level, map: &graph::GameWorld) -> String {
+    format!("{}/{}", level.name, map_id(map))
+}
+
+/// Formats seconds since the unix epoch as a yyyy-mm-dd date
+pub fn format_date(secs: u64) -> String {
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+impl Leaderboard {
+    /// Loads the table at `path`, falling back on its backup if the file is corrupted
+    pub fn load(path: &Path) -> Leaderboard {
+        storage::load(path, "leaderboard")
+    }
+
+    /// Writes the table safely, keeping the previous one as a backup
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::save(self, path)
+    }
+
+    /// Best entries for a map and seed, highest score first
+    pub fn top(&self, map: &str, seed: u32, count: usize) -> Vec<&LeaderboardEntry> {
+        let mut entries: Vec<_> = self.entries.iter()
+            .filter(|entry| entry.map == map && entry.seed == seed)
+            .collect();
+        entries.sort_by(|a, b| b.score.cmp(&a.score).then(a.lateness.total_cmp(&b.lateness)));
+        entries.truncate(count);
+        entries
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LeaderboardScreen {
+    pub open: bool,
+}
+
+#[derive(Component)]
+pub struct LeaderboardText;
+
+pub fn setup_leaderboard_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: LEADERBOARD_FONT_SIZE,
+                color: LEADERBOARD_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(80.0),
+                left: Val::Px(80.0),
+                ..default()
+            },
+            ..default()
+        }).with_background_color(LEADERBOARD_BACKGROUND_COLOR),
+        LeaderboardText,
+    ));
+}
+
+pub fn record_shift(
+    mut shift_ended: EventReader<ShiftEnded>,
+    score: Res<Score>,
+    map: Res<graph::GameWorld>,
+    current: Res<crate::levels::CurrentLevel>,
+    game_rng: Res<crate::GameRng>,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut screen: ResMut<LeaderboardScreen>,
+) {
+    for _ in shift_ended.iter() {
+        leaderboard.entries.push(LeaderboardEntry {
+            map: level_map_id(&current.level, &map),
+            seed: game_rng.seed,
+            score: score.points,
+            deliveries: score.deliveries,
+            late_deliveries: score.late_deliveries,
+            lateness: score.lateness,
+            date: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        });
+        if let Err(e) = leaderboard.save(Path::new(LEADERBOARD_PATH)) {
+            error!("could not save leaderboard to {:?}: {}", LEADERBOARD_PATH, e);
+        }
+        screen.open = true;
+    }
+}
+
+/// The map and seed a table is shown for, and the scores set on it
+#[derive(SystemParam)]
+pub struct Scores<'w> {
+    leaderboard: Res<'w, Leaderboard>,
+    map: Res<'w, graph::GameWorld>,
+    current: Res<'w, crate::levels::CurrentLevel>,
+    game_rng: Res<'w, crate::GameRng>,
+}
+
+pub fn leaderboard_screen(
+    actions: Res<ActionState>,
+    shift: Res<Shift>,
+    scores: Scores,
+    mut screen: ResMut<LeaderboardScreen>,
+    mut start_shift: EventWriter<StartShift>,
+    mut text: Query<(&mut Text, &mut Visibility), With<LeaderboardText>>,
+) {
+    let Scores { leaderboard, map, current, game_rng } = scores;
+    if actions.just_pressed(Action::Leaderboard) {
+        if shift.is_over() {
+            // closing the results of a finished shift starts the next one
+            screen.open = false;
+            start_shift.send(StartShift);
+        } else {
+            screen.open = !screen.open;
+        }
+    }
+
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else { return };
+    if !screen.open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let map_name = level_map_id(&current.level, &map);
+    let mut value = if shift.is_over() {
+        "Shift over! Press the shop key to buy upgrades, or the leaderboard key to start the next shift\n\n".to_string()
+    } else {
+        String::new()
+    };
+    value += &format!("High scores for map {} with seed {}\n", map_name, game_rng.seed);
+    value += "  #   score  deliveries  late  lateness  date\n";
+    for (rank, entry) in leaderboard.top(&map_name, game_rng.seed, SHOWN_ENTRIES).iter().enumerate() {
+        value += &format!(
+            "{:>3} {:>7} {:>11} {:>5} {:>8.1}s  {}\n",
+            rank + 1, entry.score, entry.deliveries, entry.late_deliveries, entry.lateness, format_date(entry.date),
+        );
+    }
+    text.sections[0].value = value;
+}
@@ -0,0 +1,403 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::petgraph::graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+
+use crate::closures::Closure;
+use crate::dogs::DogSpec;
+use crate::input::{Action, ActionState};
+use crate::map::MapData;
+use crate::orders::{OrderPolicy, OrderProcess};
+use crate::path_limits::PathLimits;
+use crate::scoring::Score;
+use crate::shift::{ShiftEnded, StartShift};
+use crate::subscriptions::{Subscription, SubscriptionSpec};
+use crate::throwing::{LandedPaper, Paper};
+use crate::weather::Spell;
+use crate::{graph, models};
+
+pub const CAMPAIGN_PATH: &str = "assets/levels/campaign.ron";
+pub const PROGRESS_PATH: &str = "saves/progress.ron";
+
+// gap between paperboys when a level starts with more than one
+const PAPERBOY_SPAWN_SPACING: f32 = 20.0;
+
+const LEVEL_SELECT_FONT_SIZE: f32 = 20.0;
+const LEVEL_SELECT_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+const LEVEL_SELECT_SELECTED_COLOR: Color = Color::rgb(0.2, 0.2, 1.0);
+const LEVEL_SELECT_LOCKED_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+const LEVEL_SELECT_BACKGROUND_COLOR: Color = Color::rgba(1., 1., 1., 0.9);
+
+/// Gameplay features that are switched off until a level unlocks them
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Mechanic {
+    // sending paperboys along drawn paths
+    Dispatch,
+    // length and arrival estimates while drawing a path
+    RoutePreview,
+    // moving paperboys throwing at their assigned houses by themselves
+    AutoThrow,
+    // drawing the fastest path to waiting houses along the roads
+    AutoPlan,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Level {
+    pub name: String,
+    // path of the map file, relative to the working directory
+    pub map: String,
+    pub target_score: i32,
+    pub paperboys: u32,
+    pub shift_length: f32,
+    // added together to give the rate of new orders over the shift
+    pub orders: Vec<OrderProcess>,
+    pub order_policy: OrderPolicy,
+    // houses that get a paper every shift, on top of the random orders
+    #[serde(default)]
+    pub subscriptions: Vec<SubscriptionSpec>,
+    // (seconds into the shift, daylight from 0 at midnight to 1 at noon), interpolated linearly;
+    // levels without any are in full daylight
+    #[serde(default)]
+    pub daylight: Vec<(f32, f32)>,
+    #[serde(default)]
+    pub weather: Vec<Spell>,
+    // roads shut for part of the shift
+    #[serde(default)]
+    pub closures: Vec<Closure>,
+    #[serde(default)]
+    pub dogs: Vec<DogSpec>,
+    #[serde(default)]
+    pub path_limits: PathLimits,
+    pub mechanics: Vec<Mechanic>,
+}
+
+impl Level {
+    pub fn unlocks(&self, mechanic: Mechanic) -> bool {
+        self.mechanics.contains(&mechanic)
+    }
+
+    /// A single level on the builtin map with everything unlocked, used when there is no campaign
+    pub fn sandbox() -> Level {
+        Level {
+            name: "Sandbox".to_string(),
+            map: String::new(),
+            target_score: 0,
+            paperboys: 1,
+            shift_length: crate::shift::SHIFT_LENGTH,
+            // one order every BASE_TIMER seconds on average
+            orders: vec![OrderProcess::Steady { rate: vec![(0., 60. / crate::BASE_TIMER)] }],
+            order_policy: OrderPolicy::OnePerHouse,
+            subscriptions: vec![],
+            daylight: vec![(0., 1.)],
+            weather: vec![],
+            closures: vec![],
+            dogs: vec![],
+            path_limits: PathLimits::default(),
+            mechanics: vec![Mechanic::Dispatch, Mechanic::RoutePreview, Mechanic::AutoThrow, Mechanic::AutoPlan],
+        }
+    }
+
+    pub fn load_map(&self) -> MapData {
+        if self.map.is_empty() {
+            return MapData::builtin();
+        }
+        MapData::load(Path::new(&self.map)).unwrap_or_else(|e| {
+            warn!("could not load map {:?} for level {:?}, using the builtin map: {}", self.map, self.name, e);
+            MapData::builtin()
+        })
+    }
+}
+
+/// The ordered list of levels
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Campaign {
+    pub levels: Vec<Level>,
+}
+
+impl Campaign {
+    pub fn load(path: &Path) -> Campaign {
+        let campaign = fs::read_to_string(path).map_err(|e| e.to_string())
+            .and_then(|contents| ron::from_str::<Campaign>(&contents).map_err(|e| e.to_string()));
+        match campaign {
+            Ok(campaign) if !campaign.levels.is_empty() => campaign,
+            Ok(_) => Campaign { levels: vec![Level::sandbox()] },
+            Err(e) => {
+                warn!("could not load campaign from {:?}, using a sandbox level: {}", path, e);
+                Campaign { levels: vec![Level::sandbox()] }
+            }
+        }
+    }
+}
+
+/// Names of completed levels, kept between runs
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub completed: Vec<String>,
+}
+
+impl Progress {
+    pub fn load(path: &Path) -> Progress {
+        fs::read_to_string(path).ok()
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn is_completed(&self, level: &Level) -> bool {
+        self.completed.contains(&level.name)
+    }
+
+    /// The first level is always open, every other one once the level before it is completed
+    pub fn is_unlocked(&self, campaign: &Campaign, index: usize) -> bool {
+        index == 0 || campaign.levels.get(index - 1).is_some_and(|previous| self.is_completed(previous))
+    }
+
+    /// Level to start on: the first one that hasn't been completed yet
+    pub fn next_level(&self, campaign: &Campaign) -> usize {
+        campaign.levels.iter().position(|level| !self.is_completed(level)).unwrap_or(0)
+    }
+}
+
+/// The level being played
+#[derive(Resource, Debug, Clone)]
+pub struct CurrentLevel {
+    pub index: usize,
+    pub level: Level,
+}
+
+/// Sent to tear down the current map and start the level at this index of the campaign
+pub struct LoadLevel(pub usize);
+
+pub fn start_campaign(
+    campaign: Res<Campaign>,
+    progress: Res<Progress>,
+    mut load_level: EventWriter<LoadLevel>,
+) {
+    load_level.send(LoadLevel(progress.next_level(&campaign)));
+}
+
+/// Everything a level puts on the map, cleared before loading another level or a save
+pub type LevelEntities = Or<(
+    With<models::House>,
+    With<crate::Paperboy>,
+    With<Paper>,
+    With<LandedPaper>,
+    With<crate::PathSegment>,
+    With<crate::Road>,
+    With<crate::economy::Depot>,
+    With<crate::stamina::RestSpot>,
+    With<crate::closures::Barrier>,
+    With<crate::dogs::Dog>,
+)>;
+
+/// What loading a level or a save replaces: everything on the map, and the path being drawn
+#[derive(SystemParam)]
+pub struct Replaced<'w, 's> {
+    pub old_entities: Query<'w, 's, Entity, LevelEntities>,
+    pub paths: Query<'w, 's, &'static mut crate::Path>,
+}
+
+/// What a level is set up from: its place in the campaign, and what the player has bought
+#[derive(SystemParam)]
+pub struct LevelSetup<'w> {
+    campaign: Res<'w, Campaign>,
+    profile: Res<'w, crate::economy::Profile>,
+}
+
+pub fn load_level(
+    mut commands: Commands,
+    mut load_level: EventReader<LoadLevel>,
+    setup: LevelSetup,
+    mut current: ResMut<CurrentLevel>,
+    mut start_shift: EventWriter<StartShift>,
+    mut report: ResMut<crate::reload::MapReport>,
+    replaced: Replaced,
+) {
+    let LevelSetup { campaign, profile } = setup;
+    let Replaced { old_entities, mut paths } = replaced;
+    let Some(LoadLevel(index)) = load_level.iter().last() else { return };
+    let Some(level) = campaign.levels.get(*index) else {
+        warn!("there is no level {}", index);
+        return;
+    };
+
+    info!("loading level {:?}", level.name);
+    let map = level.load_map();
+    *report = crate::reload::MapReport::validated(&map);
+    for problem in &report.problems {
+        warn!("map {:?}: {}", map.name, problem);
+    }
+
+    for entity in &old_entities {
+        commands.entity(entity).despawn();
+    }
+    if let Ok(mut path) = paths.get_single_mut() {
+        path.points.clear();
+        path.entities.clear();
+    }
+
+    let world = graph::GameWorld::from(&map.graph);
+    for closure in &level.closures {
+        let (start, end) = (NodeIndex::new(closure.edge.0), NodeIndex::new(closure.edge.1));
+        if world.graph.find_edge(start, end).or(world.graph.find_edge(end, start)).is_none() {
+            warn!("level {:?} closes road {:?}, but the map has no such road", level.name, closure.edge);
+        }
+    }
+    crate::spawn_roads(&mut commands, &world);
+    commands.insert_resource(world);
+
+    let houses: Vec<Entity> = map.houses.iter()
+        .map(|position| models::spawn_house(&mut commands, *position, models::House::new()))
+        .collect();
+    for spec in &level.subscriptions {
+        match houses.get(spec.house) {
+            Some(house) => {
+                commands.entity(*house).insert(Subscription::new(spec));
+            }
+            None => warn!("level {:?} subscribes house {}, but the map only has {}", level.name, spec.house, houses.len()),
+        }
+    }
+    crate::dogs::spawn_dogs(&mut commands, &level.dogs, &map.houses);
+    crate::economy::spawn_depot(&mut commands, map.spawn);
+    for position in &map.rest_points {
+        crate::stamina::spawn_rest_point(&mut commands, *position);
+    }
+    for i in 0..level.paperboys.max(1) + profile.upgrades.hired {
+        let paperboy = crate::spawn_paperboy(&mut commands, map.spawn + Vec2::new(i as f32 * PAPERBOY_SPAWN_SPACING, 0.));
+        if i == 0 {
+            commands.entity(paperboy).insert(crate::Selected);
+        }
+    }
+
+    *current = CurrentLevel { index: *index, level: level.clone() };
+    start_shift.send(StartShift);
+}
+
+pub fn complete_level(
+    mut shift_ended: EventReader<ShiftEnded>,
+    score: Res<Score>,
+    current: Res<CurrentLevel>,
+    mut progress: ResMut<Progress>,
+) {
+    for _ in shift_ended.iter() {
+        if score.points < current.level.target_score || progress.is_completed(&current.level) {
+            continue;
+        }
+        info!("level {:?} completed with {} points", current.level.name, score.points);
+        progress.completed.push(current.level.name.clone());
+        if let Err(e) = progress.save(Path::new(PROGRESS_PATH)) {
+            error!("could not save progress to {:?}: {}", PROGRESS_PATH, e);
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct LevelSelectScreen {
+    pub open: bool,
+    selected: usize,
+}
+
+#[derive(Component)]
+pub struct LevelSelectText;
+
+pub fn setup_level_select(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: LEVEL_SELECT_FONT_SIZE,
+                color: LEVEL_SELECT_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(60.0),
+                left: Val::Px(60.0),
+                ..default()
+            },
+            ..default()
+        }).with_background_color(LEVEL_SELECT_BACKGROUND_COLOR),
+        LevelSelectText,
+    ));
+}
+
+/// The campaign's levels, which of them are open, and the one being played
+#[derive(SystemParam)]
+pub struct Levels<'w> {
+    campaign: Res<'w, Campaign>,
+    progress: Res<'w, Progress>,
+    current: Res<'w, CurrentLevel>,
+}
+
+pub fn level_select_screen(
+    actions: Res<ActionState>,
+    keys: Res<Input<KeyCode>>,
+    levels: Levels,
+    mut screen: ResMut<LevelSelectScreen>,
+    mut load_level: EventWriter<LoadLevel>,
+    mut text: Query<(&mut Text, &mut Visibility), With<LevelSelectText>>,
+) {
+    let Levels { campaign, progress, current } = levels;
+    if actions.just_pressed(Action::LevelSelect) {
+        screen.open = !screen.open;
+        screen.selected = current.index;
+    }
+
+    let count = campaign.levels.len();
+    if screen.open {
+        if keys.just_pressed(KeyCode::Up) {
+            screen.selected = (screen.selected + count - 1) % count;
+        } else if keys.just_pressed(KeyCode::Down) {
+            screen.selected = (screen.selected + 1) % count;
+        } else if keys.just_pressed(KeyCode::Return) && progress.is_unlocked(&campaign, screen.selected) {
+            load_level.send(LoadLevel(screen.selected));
+            screen.open = false;
+        }
+    }
+
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else { return };
+    if !screen.open {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+
+    let style = text.sections[0].style.clone();
+    let mut sections = vec![TextSection::new("Levels - Up/Down to select, Enter to play\n", style.clone())];
+    for (index, level) in campaign.levels.iter().enumerate() {
+        let status = if progress.is_completed(level) {
+            "completed"
+        } else if progress.is_unlocked(&campaign, index) {
+            "unlocked"
+        } else {
+            "locked"
+        };
+        let color = if index == screen.selected {
+            LEVEL_SELECT_SELECTED_COLOR
+        } else if status == "locked" {
+            LEVEL_SELECT_LOCKED_COLOR
+        } else {
+            style.color
+        };
+        sections.push(TextSection::new(
+            format!(
+                "{}. {} - target {} points, {} paperboy(s) - {}\n",
+                index + 1, level.name, level.target_score, level.paperboys, status,
+            ),
+            TextStyle { color, ..style.clone() },
+        ));
+    }
+    text.sections = sections;
+}
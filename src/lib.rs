@@ -0,0 +1,783 @@
+//! Parallel Paperboy: route paperboys through a town to deliver papers before they're due.
+//!
+//! `SimulationPlugin` runs the game without a window, for tools and tests; `GamePlugin` adds
+//! everything needed to play it. The pure parts, such as `graph`, `orders`, `routing` and
+//! `scoring`, can also be used without Bevy's scheduler at all.
+
+use std::time::Duration;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::math::*;
+use rand::*;
+use rand::seq::IteratorRandom;
+use rand_chacha::ChaCha8Rng;
+use bevy::utils::FloatOrd;
+
+pub mod camera;
+pub mod closures;
+pub mod debug;
+pub mod dogs;
+pub mod economy;
+pub mod graph;
+pub mod input;
+pub mod leaderboard;
+pub mod levels;
+pub mod map;
+pub mod models;
+pub mod movement;
+pub mod orders;
+pub mod path_limits;
+pub mod reload;
+pub mod route;
+pub mod routing;
+pub mod save;
+pub mod scoring;
+pub mod shift;
+pub mod spatial;
+pub mod stamina;
+pub mod storage;
+pub mod subscriptions;
+pub mod throwing;
+pub mod validate;
+pub mod vehicle;
+pub mod weather;
+
+const WALL_THICKNESS: f32 = 10.0;
+// x coordinates
+const LEFT_WALL: f32 = -450.;
+const RIGHT_WALL: f32 = 450.;
+// y coordinates
+const BOTTOM_WALL: f32 = -300.;
+const TOP_WALL: f32 = 300.;
+
+//const HOUSE_SIZE: Vec2 = Vec2::new(10., 10.);
+const PAPERBOY_SIZE: Vec2 = Vec2::new(10., 10.);
+// world units per second
+const PAPERBOY_SPEED: f32 = 60.0;
+// how far from a porch a paperboy can throw a paper
+pub const PAPERBOY_REACH: f32 = 20.0;
+// how close to a paperboy a click has to be to select it
+const PAPERBOY_SELECT_RADIUS: f32 = 15.0;
+
+// DejaVu Sans, under the license in assets/fonts/LICENSE
+const FONT_PATH: &str = "fonts/DejaVuSans.ttf";
+
+pub const BACKGROUND_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const ROAD_COLOR: Color = Color::rgb(0., 0., 0.);
+const FOOTPATH_COLOR: Color = Color::rgb(0.55, 0.45, 0.3);
+const PATH_COLOR: Color = Color::rgb(1.0, 0.2, 0.2);
+const PAPERBOY_COLOR: Color = Color::rgb(0.2, 0.2, 1.0);
+const PAPERBOY_HIGHLIGHT_COLOR: Color = Color::rgb(0.2, 0.9, 0.2);
+//const TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+//const HOUSE_COLOR: Color = Color::rgb(0.84, 0.13, 0.13);
+//const ORIGIN_COLOR: Color = Color::rgb(0., 0., 0.);
+const WALL_COLOR: Color = Color::rgb(0., 0., 0.);
+
+#[derive(Resource)]
+pub struct NewDeliveryTimer(pub Timer);
+
+/// Seeded source of all gameplay randomness, so a shift can be saved and replayed exactly
+#[derive(Resource)]
+pub struct GameRng {
+    pub seed: u32,
+    pub rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u32) -> GameRng {
+        GameRng { seed, rng: ChaCha8Rng::seed_from_u64(seed as u64) }
+    }
+
+    pub fn from_entropy() -> GameRng {
+        GameRng::from_seed(rand::thread_rng().gen())
+    }
+}
+
+const BASE_TIMER: f32 = 10.0;
+
+/// Inserts the resource `load` gives, unless the app already has one
+fn insert_if_missing<R: Resource>(app: &mut App, load: impl FnOnce() -> R) {
+    if !app.world.contains_resource::<R>() {
+        let resource = load();
+        app.insert_resource(resource);
+    }
+}
+
+/// The game's rules and state with nothing drawn: maps, orders, paperboys, papers, scoring,
+/// shifts and the campaign, driven by `input::ActionState` and `camera::CursorWorldPosition`.
+/// Runs headless on top of `MinimalPlugins` and `InputPlugin`. Campaign, profile, progress,
+/// leaderboard, key bindings and the random seed are loaded from disk unless inserted first.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        insert_if_missing(app, || levels::Campaign::load(std::path::Path::new(levels::CAMPAIGN_PATH)));
+        let first_level = app.world.resource::<levels::Campaign>().levels[0].clone();
+        insert_if_missing(app, || levels::CurrentLevel { index: 0, level: first_level });
+        insert_if_missing(app, || levels::Progress::load(std::path::Path::new(levels::PROGRESS_PATH)));
+        insert_if_missing(app, || economy::Profile::load(std::path::Path::new(economy::PROFILE_PATH)));
+        insert_if_missing(app, || leaderboard::Leaderboard::load(std::path::Path::new(leaderboard::LEADERBOARD_PATH)));
+        insert_if_missing(app, || input::KeyBindings::load(std::path::Path::new(input::BINDINGS_PATH)));
+        insert_if_missing(app, GameRng::from_entropy);
+
+        app
+            .init_resource::<graph::GameWorld>()
+            .insert_resource(NewDeliveryTimer(Timer::from_seconds(BASE_TIMER, TimerMode::Repeating)))
+            .init_resource::<leaderboard::LeaderboardScreen>()
+            .init_resource::<shift::Shift>()
+            .add_event::<shift::ShiftEnded>()
+            .add_event::<shift::StartShift>()
+            .init_resource::<levels::LevelSelectScreen>()
+            .init_resource::<economy::ShopScreen>()
+            .init_resource::<weather::Conditions>()
+            .init_resource::<spatial::SpatialIndex>()
+            .init_resource::<reload::MapWatcher>()
+            .init_resource::<reload::MapReport>()
+            .init_resource::<path_limits::PathCheck>()
+            .add_event::<levels::LoadLevel>()
+            .add_event::<routing::Reroute>()
+            .init_resource::<input::ActionState>()
+            .init_resource::<input::RebindingScreen>()
+            .init_resource::<throwing::AutoThrow>()
+            .init_resource::<scoring::Score>()
+            .init_resource::<camera::CursorWorldPosition>()
+            .init_resource::<camera::CameraFollow>()
+            .add_startup_system(setup_simulation)
+            .add_startup_system(levels::start_campaign)
+            .add_system(input::update_action_state.in_base_set(CoreSet::PreUpdate).after(bevy::input::InputSystem))
+            .add_system(activate_new_destination)
+            .add_system(spatial::update_spatial_index
+                .after(reload::reload_map)
+                .before(delivery_command)
+                .before(mouse_button_place_paperboy))
+            .add_system(delivery_command)
+            .add_system(mouse_button_place_paperboy.after(camera::CursorUpdate))
+            .add_system(mouse_button_place_path.after(camera::CursorUpdate))
+            .add_system(select_next_paperboy)
+            .add_system(path_limits::check_path
+                .after(mouse_button_place_path)
+                .after(delivery_command)
+                .after(routing::plan_route)
+                .after(save::load_game))
+            .add_system(movement::dispatch_paperboy.after(path_limits::check_path))
+            .add_system(throwing::toggle_auto_throw)
+            .add_system(closures::update_closures.after(shift::tick_shift))
+            .add_system(closures::stop_blocked_paperboys.after(closures::update_closures).after(movement::dispatch_paperboy))
+            .add_system(routing::reroute_paperboys.after(closures::stop_blocked_paperboys).after(spatial::update_spatial_index))
+            .add_system(movement::move_paperboys.after(routing::reroute_paperboys))
+            .add_system(dogs::mark_dog_territory.after(closures::update_closures))
+            .add_system(dogs::chase_paperboys.after(spatial::update_spatial_index).after(movement::move_paperboys))
+            .add_system(throwing::auto_throw.after(spatial::update_spatial_index).after(movement::move_paperboys))
+            .add_system(throwing::move_papers)
+            .add_system(throwing::land_papers.after(throwing::move_papers))
+            .add_system(throwing::clear_landed_papers)
+            .add_system(subscriptions::subscription_orders)
+            .add_system(subscriptions::miss_subscriptions.after(shift::tick_shift))
+            .add_system(subscriptions::reset_subscriptions)
+            .add_system(subscriptions::cancel_subscriptions.after(subscriptions::miss_subscriptions).after(throwing::land_papers))
+            .add_system(economy::equip_bags)
+            .add_system(vehicle::equip_vehicles)
+            .add_system(stamina::equip_stamina)
+            .add_system(weather::update_conditions.after(shift::tick_shift))
+            .add_system(stamina::rest_paperboys.after(movement::move_paperboys))
+            .add_system(stamina::rest_between_shifts)
+            .add_system(routing::plan_route.after(spatial::update_spatial_index))
+            .add_system(economy::refill_bags)
+            .add_system(economy::restock_bags)
+            .add_system(economy::bank_earnings.after(shift::tick_shift))
+            .add_system(economy::shop_screen)
+            .add_system(shift::tick_shift)
+            .add_system(leaderboard::record_shift.after(shift::tick_shift))
+            .add_system(levels::complete_level.after(shift::tick_shift))
+            .add_system(leaderboard::leaderboard_screen.after(leaderboard::record_shift))
+            .add_system(levels::level_select_screen)
+            .add_system(levels::load_level.after(levels::level_select_screen))
+            .add_system(reload::reload_map.after(levels::load_level).after(save::load_game))
+            .add_system(shift::start_new_shift.after(leaderboard::leaderboard_screen).after(levels::load_level))
+            .add_system(save::save_game)
+            .add_system(save::load_game);
+    }
+}
+
+/// The windowed game: the simulation, plus the camera, HUD, menus and everything drawn only to
+/// be looked at. Needs `DefaultPlugins`.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugin(SimulationPlugin)
+            .insert_resource(ClearColor(BACKGROUND_COLOR))
+            .init_resource::<debug::DebugOverlay>()
+            .init_resource::<debug::DebugShapes>()
+            .add_startup_system(setup_drawing_map)
+            .add_startup_system(levels::setup_level_select)
+            .add_startup_system(economy::setup_shop)
+            .add_startup_system(weather::setup_tints)
+            .add_startup_system(route::setup_route_preview)
+            .add_startup_system(input::setup_rebinding_screen)
+            .add_startup_system(scoring::setup_score)
+            .add_startup_system(leaderboard::setup_leaderboard_screen)
+            .add_startup_system(reload::setup_map_report)
+            .add_system(input::rebinding_screen)
+            .add_systems((
+                camera::toggle_camera_follow,
+                camera::pan_camera,
+                camera::zoom_camera,
+                camera::follow_selected_paperboy,
+                camera::update_cursor_world_position.in_set(camera::CursorUpdate),
+            ).chain())
+            .add_system(highlight_selected_paperboy.after(mouse_button_place_paperboy).after(select_next_paperboy))
+            .add_system(route::preview_route.after(path_limits::check_path))
+            .add_system(path_limits::colour_path_segments.after(path_limits::check_path))
+            .add_system(scoring::update_score_text)
+            .add_system(models::update_order_labels)
+            .add_system(reload::update_map_report_text.after(reload::reload_map))
+            .add_system(subscriptions::update_subscription_markers)
+            .add_system(weather::update_tints.after(weather::update_conditions))
+            .add_system(weather::update_shrouds.after(weather::update_conditions))
+            .add_system(debug::toggle_debug_overlay)
+            .add_system(debug::draw_debug_overlay.after(debug::toggle_debug_overlay))
+            // after everything that draws into the shapes this frame
+            .add_system(debug::render_debug_shapes
+                .in_base_set(CoreSet::PostUpdate)
+                .before(bevy::render::view::VisibilitySystems::VisibilityPropagate))
+            .add_system(bevy::window::close_on_esc);
+    }
+}
+
+
+
+
+fn random_position() -> Vec2 {
+    let xPosOrNeg: f32 = rand::thread_rng().gen();
+    let yPosOrNeg: f32 = rand::thread_rng().gen();
+    let xRng: f32 = rand::thread_rng().gen();
+    let yRng: f32 = rand::thread_rng().gen();
+
+    let mut x: f32;
+    let mut y: f32;
+
+    if (xPosOrNeg > 0.5) {
+        x = xRng * 10f32;
+    } else {
+        x = xRng * -10f32;
+    }
+
+    if (yPosOrNeg > 0.5) {
+        y = yRng * 10f32;
+    } else {
+        y = yRng * -10f32;
+    }
+
+    return Vec2::new(x, y)
+}
+
+fn activate_new_destination(
+    time: Res<Time>, mut timer: ResMut<NewDeliveryTimer>, mut game_rng: ResMut<GameRng>, shift: Res<shift::Shift>, current: Res<levels::CurrentLevel>, mut query: Query<(Entity, &mut models::House, &mut Sprite, &Transform)>) {
+    // update our timer with the time elapsed since the last update
+    // if that caused the timer to finish, the level's order processes say what happens
+    if timer.0.tick(time.delta()).just_finished() {
+        let rng = &mut game_rng.rng;
+        let level = &current.level;
+        let elapsed = shift.timer.elapsed_secs();
+        let event = orders::pick_event(&level.orders, elapsed, rng);
+        // no more orders this shift leaves the timer running past the end of it
+        let gap = orders::next_gap(&level.orders, elapsed, level.shift_length, rng).unwrap_or(level.shift_length);
+        timer.0.set_duration(Duration::from_secs_f32(gap));
+
+        match event {
+            orders::OrderEvent::Single => {
+                if let Some((_, mut house, mut sprite, _)) = query.iter_mut()
+                    .filter(|(_, house, _, _)| level.order_policy.accepts(house))
+                    .choose(rng)
+                {
+                    models::place_order(&mut house, &mut sprite, time.elapsed_seconds());
+                } else {
+                    debug!("no house can take another order, dropping it");
+                }
+            }
+            orders::OrderEvent::Burst { size, radius } => {
+                let candidates: Vec<(Entity, Vec2)> = query.iter()
+                    .filter(|(_, house, _, _)| level.order_policy.accepts(house))
+                    .map(|(entity, _, _, transform)| (entity, transform.translation.truncate()))
+                    .collect();
+                let burst = orders::burst_houses(&candidates, size, radius, rng);
+                debug!("{} houses ordering at once: {:?}", burst.len(), burst);
+                for entity in burst {
+                    if let Ok((_, mut house, mut sprite, _)) = query.get_mut(entity) {
+                        models::place_order(&mut house, &mut sprite, time.elapsed_seconds());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+    PlacingPaperboy,
+    PlacingPath,
+    Paused,
+}
+
+#[derive(Component)]
+pub struct UIState {
+    pub selection_mode: SelectionMode,
+    // mode to go back to when unpausing
+    pub paused_from: SelectionMode,
+}
+
+impl UIState {
+    pub fn new() -> UIState {
+        UIState { selection_mode: SelectionMode::PlacingPaperboy, paused_from: SelectionMode::PlacingPaperboy }
+    }
+}
+
+impl Default for UIState {
+    fn default() -> UIState { UIState::new() }
+}
+
+#[derive(Component)]
+pub struct Collider;
+
+#[derive(Component)]
+pub struct Paperboy;
+
+/// The paperboy that the camera follows and that clicks and commands apply to
+#[derive(Component)]
+pub struct Selected;
+
+/// Filters a query down to the selected paperboy
+pub type SelectedPaperboy = (With<Paperboy>, With<Selected>);
+
+#[derive(Component)]
+pub struct Road;
+
+#[derive(Component, Debug)]
+pub struct Path {
+    pub points: Vec<Vec2>,
+    pub entities: Vec<Entity>,
+}
+
+impl Path {
+    pub fn new() -> Path {
+        Path { points: vec![], entities: vec![] }
+    }
+}
+
+impl Default for Path {
+    fn default() -> Path { Path::new() }
+}
+
+#[derive(Component, Debug)]
+pub struct PathSegment;
+
+// #[derive(Component, Debug)]
+// struct PathHolder {
+//     paths: Vec<Path>,
+// }
+
+// impl PathHolder {
+//     pub fn new() -> PathHolder {
+//         PathHolder { paths: vec![] }
+//     }
+// }
+
+// This bundle is a collection of the components that define a "wall" in our game
+#[derive(Bundle)]
+struct WallBundle {
+    // You can nest bundles inside of other bundles like this
+    // Allowing you to compose their functionality
+    sprite_bundle: SpriteBundle,
+    collider: Collider,
+}
+
+/// Which side of the arena is this wall located on?
+enum WallLocation {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl WallLocation {
+    fn position(&self) -> Vec2 {
+        match self {
+            WallLocation::Left => Vec2::new(LEFT_WALL, 0.),
+            WallLocation::Right => Vec2::new(RIGHT_WALL, 0.),
+            WallLocation::Bottom => Vec2::new(0., BOTTOM_WALL),
+            WallLocation::Top => Vec2::new(0., TOP_WALL),
+        }
+    }
+
+    fn size(&self) -> Vec2 {
+        let arena_height = TOP_WALL - BOTTOM_WALL;
+        let arena_width = RIGHT_WALL - LEFT_WALL;
+        // Make sure we haven't messed up our constants
+        assert!(arena_height > 0.0);
+        assert!(arena_width > 0.0);
+
+        match self {
+            WallLocation::Left | WallLocation::Right => {
+                Vec2::new(WALL_THICKNESS, arena_height + WALL_THICKNESS)
+            }
+            WallLocation::Bottom | WallLocation::Top => {
+                Vec2::new(arena_width + WALL_THICKNESS, WALL_THICKNESS)
+            }
+        }
+    }
+}
+
+impl WallBundle {
+    // This "builder method" allows us to reuse logic across our wall entities,
+    // making our code easier to read and less prone to bugs when we change the logic
+    fn new(location: WallLocation) -> WallBundle {
+        WallBundle {
+            sprite_bundle: SpriteBundle {
+                transform: Transform {
+                    // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
+                    // This is used to determine the order of our sprites
+                    translation: location.position().extend(0.0),
+                    // The z-scale of 2D objects must always be 1.0,
+                    // or their ordering will be affected in surprising ways.
+                    // See https://github.com/bevyengine/bevy/issues/4149
+                    scale: location.size().extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    ..default()
+                },
+                ..default()
+            },
+            collider: Collider,
+        }
+    }
+}
+
+const SCALEUP_FACTOR: f32 = 40. as f32;
+const ROAD_THICKNESS: f32 = 20. as f32;
+const FOOTPATH_THICKNESS: f32 = 8.;
+
+fn mouse_button_place_path(
+    mut commands: Commands,
+    cursor: Res<camera::CursorWorldPosition>,
+    actions: Res<input::ActionState>,
+    ui_state: Query<&UIState>,
+    mut paths: Query<&mut Path>,
+) {
+    if ui_state.single().selection_mode != SelectionMode::PlacingPath {
+        // this method doesn't run in that mode
+        return
+    }
+
+    if actions.just_pressed(input::Action::Place) {
+        // only place things when the cursor is inside the window
+        if let Some(world_position) = cursor.0 {
+            debug!("placing a path point at {}", world_position);
+            for mut path in &mut paths {
+                let len = path.points.len();
+                if len >= 1 {
+                    let segment = spawn_path_segment(&mut commands, path.points[len-1], world_position);
+                    path.entities.push(segment);
+                }
+                path.points.push(Vec2::new(world_position.x, world_position.y));
+                debug!("path points: {:?}", path.points);
+            }
+        }
+    }
+}
+
+pub fn spawn_path_segment(commands: &mut Commands, from: Vec2, to: Vec2) -> Entity {
+    let path_position = Vec2::new((from.x+to.x)/2., (from.y+to.y)/2.);
+    let xlen = from.x-to.x;
+    let ylen = from.y-to.y;
+    let pythagorean_len = (xlen*xlen+ylen*ylen).sqrt();
+    let path_scale = Vec2::new(pythagorean_len, ROAD_THICKNESS);
+    let path_sides_ratio = (from.y-to.y).atan2(from.x-to.x);
+    trace!("path position: {:?}", path_position);
+    trace!("path scale: {:?}", path_scale);
+    trace!("path sides ratio: {:?}", path_sides_ratio);
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: PATH_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                translation: path_position.extend(0.0),
+                scale: path_scale.extend(0.0),
+                rotation: Quat::from_rotation_z(path_sides_ratio),
+                ..default()
+            },
+            ..default()
+        },
+        PathSegment,
+        Collider,
+    )).id()
+}
+
+fn mouse_button_place_paperboy(
+    mut commands: Commands,
+    cursor: Res<camera::CursorWorldPosition>,
+    actions: Res<input::ActionState>,
+    index: Res<spatial::SpatialIndex>,
+    mut paperboy_transform: Query<(Entity, &mut Transform, Option<&Selected>), With<Paperboy>>,
+    ui_state: Query<&UIState>,
+) {
+    if ui_state.single().selection_mode != SelectionMode::PlacingPaperboy {
+        // this method doesn't run in that mode
+        return
+    }
+
+    if actions.just_pressed(input::Action::Place) {
+        // only place things when the cursor is inside the window
+        if let Some(world_position) = cursor.0 {
+            debug!("placing the paperboy at {}", world_position);
+            // clicking on a paperboy selects it, clicking anywhere else moves the selected one there
+            let clicked = index.paperboys.within(world_position, PAPERBOY_SELECT_RADIUS).first().copied();
+            if let Some((clicked, _)) = clicked {
+                for (entity, _, selected) in &paperboy_transform {
+                    if entity == clicked {
+                        commands.entity(entity).insert(Selected);
+                    } else if selected.is_some() {
+                        commands.entity(entity).remove::<Selected>();
+                    }
+                }
+                debug!("selected paperboy {:?}", clicked);
+                return;
+            }
+            for (_, mut transform, selected) in &mut paperboy_transform {
+                if selected.is_some() {
+                    transform.translation.x = world_position.x;
+                    transform.translation.y = world_position.y;
+                }
+            }
+        }
+    }
+}
+
+/// What the deliver key throws with: the selected paperboy's bag, the houses near it, and the
+/// rng for the throw
+#[derive(SystemParam)]
+struct Delivery<'w, 's> {
+    game_rng: ResMut<'w, GameRng>,
+    index: Res<'w, spatial::SpatialIndex>,
+    paperboy_transform: Query<'w, 's, (&'static Transform, &'static mut economy::Bag), SelectedPaperboy>,
+    all_houses: Query<'w, 's, (&'static models::House, &'static Transform, Option<&'static subscriptions::Subscription>)>,
+}
+
+fn delivery_command(
+    mut commands: Commands,
+    mut time: ResMut<Time>,
+    shift: Res<shift::Shift>,
+    actions: Res<input::ActionState>,
+    mut ui_states: Query<&mut UIState>,
+    mut paths: Query<&mut Path>,
+    delivery: Delivery,
+) {
+    let Delivery { mut game_rng, index, mut paperboy_transform, all_houses } = delivery;
+    if shift.is_over() {
+        // everything stays frozen until the next shift is started from the leaderboard
+    } else if actions.just_pressed(input::Action::Pause) {
+        for mut ui_state in &mut ui_states {
+            if ui_state.selection_mode == SelectionMode::Paused {
+                ui_state.selection_mode = ui_state.paused_from;
+                time.unpause();
+            } else {
+                ui_state.paused_from = ui_state.selection_mode;
+                ui_state.selection_mode = SelectionMode::Paused;
+                time.pause();
+            }
+        }
+        debug!("pause pressed, UI state is {:?}", ui_states.single().selection_mode);
+    } else if ui_states.single().selection_mode == SelectionMode::Paused {
+        // nothing else can be done while paused
+    } else if actions.just_pressed(input::Action::Deliver) {
+        let rng = &mut game_rng.rng;
+        for (transform, mut bag) in &mut paperboy_transform {
+            let position = transform.translation.truncate();
+            if bag.papers == 0 {
+                info!("deliver pressed, but the bag is empty, go back to the depot");
+                continue;
+            }
+            // only houses close enough to have an aim point in reach need looking at
+            if let Some(porch) = index.houses.within(position, PAPERBOY_REACH + subscriptions::MAX_AIM_OFFSET).iter()
+                .filter_map(|(house, _)| all_houses.get(*house).ok())
+                .filter(|(house, _, _)| house.is_active())
+                .map(|(_, house_transform, subscription)| subscriptions::aim_point(house_transform.translation.truncate(), subscription))
+                .min_by_key(|porch| FloatOrd(porch.distance(position)))
+            {
+                if porch.distance(position) < PAPERBOY_REACH {
+                    // standing still makes for an accurate throw
+                    bag.papers -= 1;
+                    throwing::throw_paper(&mut commands, position, porch, 0., rng);
+                    debug!("deliver pressed, paperboy at {}, throwing a paper at {}", position, porch);
+                } else {
+                    debug!("deliver pressed, paperboy at {}, no active house in range", position);
+                }
+            }
+        }
+    } else if actions.just_pressed(input::Action::CycleMode) {
+        debug!("cycle mode pressed, UI state is {:?}", ui_states.single().selection_mode);
+        for mut ui_state in &mut ui_states {
+            ui_state.selection_mode =  match ui_state.selection_mode {
+                SelectionMode::PlacingPaperboy => SelectionMode::PlacingPath,
+                SelectionMode::PlacingPath => SelectionMode::PlacingPaperboy,
+                SelectionMode::Paused => SelectionMode::Paused
+            }
+        }
+    } else if actions.just_pressed(input::Action::Undo) {
+        if ui_states.single().selection_mode == SelectionMode::PlacingPath {
+            for mut path in &mut paths {
+                path.points.pop();
+                // the segment leading to the removed point goes with it
+                if path.entities.len() > path.points.len().saturating_sub(1) {
+                    if let Some(entity) = path.entities.pop() {
+                        commands.entity(entity).despawn();
+                    }
+                }
+            }
+            debug!("undo pressed, paths is now {:?}", paths.single().points);
+        }
+    } else if actions.just_pressed(input::Action::ClearPath) {
+        for mut path in &mut paths {
+            for entity in &path.entities {
+                commands.entity(*entity).despawn()
+            }
+            path.points.clear();
+            path.entities.clear();
+        }
+        debug!("clear path pressed, paths emptied");
+    }
+}
+
+/// Moves the selection on to the next paperboy, wrapping around
+fn select_next_paperboy(
+    mut commands: Commands,
+    actions: Res<input::ActionState>,
+    paperboys: Query<(Entity, Option<&Selected>), With<Paperboy>>,
+) {
+    if !actions.just_pressed(input::Action::NextPaperboy) {
+        return;
+    }
+    let mut entities: Vec<(Entity, bool)> = paperboys.iter().map(|(entity, selected)| (entity, selected.is_some())).collect();
+    if entities.is_empty() {
+        return;
+    }
+    // query order isn't stable, entity order is
+    entities.sort_by_key(|(entity, _)| *entity);
+    let current = entities.iter().position(|(_, selected)| *selected);
+    let next = current.map_or(0, |index| (index + 1) % entities.len());
+    if let Some(index) = current {
+        commands.entity(entities[index].0).remove::<Selected>();
+    }
+    commands.entity(entities[next].0).insert(Selected);
+    debug!("selected paperboy {:?}", entities[next].0);
+}
+
+type PaperboyLook<'a> = (&'a mut Sprite, Option<&'a Selected>, Option<&'a vehicle::Vehicle>);
+
+fn highlight_selected_paperboy(mut paperboys: Query<PaperboyLook, With<Paperboy>>) {
+    for (mut sprite, selected, vehicle) in &mut paperboys {
+        sprite.color = match (selected, vehicle) {
+            (Some(_), _) => PAPERBOY_HIGHLIGHT_COLOR,
+            (None, Some(vehicle)) => vehicle.color,
+            (None, None) => PAPERBOY_COLOR,
+        };
+    }
+}
+
+fn setup_simulation(
+    mut commands: Commands,
+) {
+    // Path holder
+    commands.spawn(Path::new());
+
+    // UIState
+    commands.spawn(UIState::new());
+}
+
+fn setup_drawing_map(
+    mut commands: Commands,
+) {
+    // Camera
+    commands.spawn((Camera2dBundle::default(), camera::MainCamera));
+
+    // Walls
+    commands.spawn(WallBundle::new(WallLocation::Left));
+    commands.spawn(WallBundle::new(WallLocation::Right));
+    commands.spawn(WallBundle::new(WallLocation::Bottom));
+    commands.spawn(WallBundle::new(WallLocation::Top));
+}
+
+/// Spawns a road sprite for every edge of the graph
+pub fn spawn_roads(commands: &mut Commands, map: &graph::GameWorld) {
+    let total_width_of_map = RIGHT_WALL - LEFT_WALL;
+    let total_height_of_map = TOP_WALL - BOTTOM_WALL;
+
+    assert!(total_width_of_map > 0.0);
+    assert!(total_height_of_map > 0.0);
+    trace!("width of map: {:?}", total_width_of_map);
+    trace!("height of map: {:?}", total_height_of_map);
+
+
+    for edge_index in map.graph.edge_indices() {
+      trace!("edge: {:?}", edge_index);
+      if let Some(endpoints) = map.graph.edge_endpoints(edge_index) {
+        let (start_index, end_index) = endpoints;
+        let start_pos = map.graph.node_weight(start_index).unwrap().pos;
+        let end_pos = map.graph.node_weight(end_index).unwrap().pos;
+        trace!("start_pos: {:?}", start_pos);
+        trace!("end_pos: {:?}", end_pos);
+        let offset = Vec2::new(total_width_of_map/4., total_height_of_map/2.);
+        let road_position = Vec2::new(
+            (start_pos.x*SCALEUP_FACTOR+end_pos.x*SCALEUP_FACTOR)/2.-offset.x,
+            (start_pos.y*SCALEUP_FACTOR+end_pos.y*SCALEUP_FACTOR)/2.);
+        trace!("road_position: {:?}", road_position);
+
+        let (thickness, color) = match map.graph[edge_index].kind {
+            graph::EdgeKind::Road => (ROAD_THICKNESS, ROAD_COLOR),
+            graph::EdgeKind::Footpath => (FOOTPATH_THICKNESS, FOOTPATH_COLOR),
+        };
+        let road_scale = Vec3::new(start_pos.x*SCALEUP_FACTOR-end_pos.x*SCALEUP_FACTOR+thickness, start_pos.y*SCALEUP_FACTOR-end_pos.y*SCALEUP_FACTOR+thickness, 1.0);
+        trace!("road_scale: {:?}", road_scale);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: road_position.extend(0.0),
+                    scale: road_scale,
+                    ..default()
+                },
+                ..default()
+            },
+            Road,
+            Collider,
+        ));
+      }
+    }
+}
+
+pub fn spawn_paperboy(commands: &mut Commands, position: Vec2) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: PAPERBOY_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.0),
+                scale: PAPERBOY_SIZE.extend(0.0),
+                ..default()
+            },
+            ..default()
+        },
+        Paperboy,
+        Collider,
+    )).id()
+}
@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::graph;
+
+/// A playable map as stored in `assets/maps`: the road graph in graph units, and houses, the
+/// paperboys' starting point and the spots they can rest at in world coordinates
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapData {
+    pub name: String,
+    pub graph: graph::GraphData,
+    pub houses: Vec<Vec2>,
+    pub spawn: Vec2,
+    // maps from before paperboys could rest don't have any
+    #[serde(default)]
+    pub rest_points: Vec<Vec2>,
+}
+
+impl MapData {
+    pub fn load(path: &Path) -> Result<MapData, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let map: MapData = ron::from_str(&contents).map_err(|e| e.to_string())?;
+        // the graph can't even be built with an edge to a node that isn't there
+        let nodes = map.graph.nodes.len();
+        for (start, end) in map.graph.edges.iter().chain(&map.graph.footpaths) {
+            if *start >= nodes || *end >= nodes {
+                return Err(format!("edge ({}, {}) refers to a node past the {} the map has", start, end, nodes));
+            }
+        }
+        Ok(map)
+    }
+
+    /// The original test map, used when a map file can't be read
+    pub fn builtin() -> MapData {
+        MapData {
+            name: "builtin".to_string(),
+            graph: graph::GraphData::from(&graph::create_graph()),
+            houses: crate::models::default_house_positions(),
+            spawn: Vec2::new(0., 20.),
+            rest_points: vec![],
+        }
+    }
+}
@@ -10,15 +10,92 @@ impl Paperboy {
     fn start(self, path: Path) {}
 }
 
+/// One paper a house is waiting for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Order {
+    // elapsed seconds at which the order is due
+    pub deadline: f32,
+    // placed by the house's subscription rather than ordered on the day
+    pub subscription: bool,
+}
+
 #[derive(Component, Debug)]
 pub struct House {
-    pub active: bool
+    // outstanding orders, oldest first
+    pub orders: Vec<Order>,
+    // paperboy whose route was planned to deliver the orders
+    pub assigned_to: Option<Entity>,
 }
 
-pub(crate) const HOUSE_COLOR: Color = Color::rgb(0., 0., 0.);
-pub(crate) const ACTIVE_HOUSE_COLOR: Color = Color::rgb(1., 0., 0.);
+impl Default for House {
+    fn default() -> House { House::new() }
+}
+
+impl House {
+    pub fn new() -> House {
+        House { orders: vec![], assigned_to: None }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.orders.is_empty()
+    }
+
+    /// When the oldest order is due
+    pub fn deadline(&self) -> Option<f32> {
+        self.orders.first().map(|order| order.deadline)
+    }
+}
 
-pub fn initialize_houses(mut commands: Commands) {
+pub const HOUSE_COLOR: Color = Color::rgb(0., 0., 0.);
+pub const ACTIVE_HOUSE_COLOR: Color = Color::rgb(1., 0., 0.);
+pub const LATE_HOUSE_COLOR: Color = Color::rgb(1., 0.6, 0.);
+
+// seconds a customer is willing to wait after placing an order
+pub const ORDER_DEADLINE: f32 = 30.0;
+
+const ORDER_LABEL_FONT_SIZE: f32 = 20.0;
+const ORDER_LABEL_COLOR: Color = Color::rgb(1., 1., 1.);
+
+pub const HOUSE_SIZE: Vec2 = Vec2::new(45., 60.);
+// depth of the strip in front of (below) a house where a paper counts as on the porch
+pub const PORCH_DEPTH: f32 = 15.;
+
+/// Center of the porch in front of the house at `house_position`, which is what papers are aimed at
+pub fn porch_position(house_position: Vec2) -> Vec2 {
+    house_position - Vec2::new(0., (HOUSE_SIZE.y + PORCH_DEPTH) / 2.)
+}
+
+pub fn add_order(house: &mut House, sprite: &mut Sprite, order: Order) {
+    house.orders.push(order);
+    sprite.color = ACTIVE_HOUSE_COLOR;
+}
+
+/// Gives the house another order, due `ORDER_DEADLINE` seconds after `now`
+pub fn place_order(house: &mut House, sprite: &mut Sprite, now: f32) {
+    add_order(house, sprite, Order { deadline: now + ORDER_DEADLINE, subscription: false });
+}
+
+/// Fills the oldest order, returning it
+pub fn mark_delivered(house: &mut House, sprite: &mut Sprite) -> Option<Order> {
+    if house.orders.is_empty() {
+        return None;
+    }
+    let order = house.orders.remove(0);
+    if house.orders.is_empty() {
+        house.assigned_to = None;
+        sprite.color = HOUSE_COLOR;
+    }
+    Some(order)
+}
+
+/// Drops every order, as at the start of a shift
+pub fn clear_orders(house: &mut House, sprite: &mut Sprite) {
+    house.orders.clear();
+    house.assigned_to = None;
+    sprite.color = HOUSE_COLOR;
+}
+
+pub fn default_house_positions() -> Vec<Vec2> {
     let mut HOUSE_POSITIONS: Vec<Vec2> = Vec::new();
 
     // HOUSE POSITIONS
@@ -31,21 +108,69 @@ pub fn initialize_houses(mut commands: Commands) {
     HOUSE_POSITIONS.push(vec2(45.0, 45.0));
     HOUSE_POSITIONS.push(vec2(-10.0, 45.0));
 
-    let scale = Vec3::new(45.0, 60.0, 0.0);
-    for pos in HOUSE_POSITIONS {
+    HOUSE_POSITIONS
+}
+
+pub fn spawn_house(commands: &mut Commands, position: Vec2, house: House) -> Entity {
+    let color = if house.is_active() { ACTIVE_HOUSE_COLOR } else { HOUSE_COLOR };
+    commands.spawn((
+        house,
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.0),
+                scale: HOUSE_SIZE.extend(0.0),
+                ..default()
+            },
+            ..default()
+        },
+        crate::Collider)).id()
+}
+
+/// Text over a house showing how many papers it is waiting for, when more than one
+#[derive(Component)]
+pub struct OrderCountLabel {
+    house: Entity,
+}
+
+/// Keeps a count label on every house, and gets rid of labels whose house has been despawned
+pub fn update_order_labels(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    houses: Query<(Entity, &House, &Transform)>,
+    mut labels: Query<(Entity, &OrderCountLabel, &mut Text)>,
+) {
+    for (label_entity, label, mut text) in &mut labels {
+        match houses.get(label.house) {
+            Ok((_, house, _)) => {
+                let count = house.orders.len();
+                text.sections[0].value = if count > 1 { count.to_string() } else { String::new() };
+            }
+            Err(_) => commands.entity(label_entity).despawn(),
+        }
+    }
+
+    for (house_entity, _, transform) in &houses {
+        if labels.iter().any(|(_, label, _)| label.house == house_entity) {
+            continue;
+        }
         commands.spawn((
-            House { active: false },
-            SpriteBundle {
-                sprite: Sprite {
-                    color: HOUSE_COLOR,
-                    ..default()
-                },
-                transform: Transform {
-                    translation: pos.extend(0.0),
-                    scale: scale,
-                    ..default()
-                },
+            Text2dBundle {
+                text: Text::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(crate::FONT_PATH),
+                        font_size: ORDER_LABEL_FONT_SIZE,
+                        color: ORDER_LABEL_COLOR,
+                    },
+                ).with_alignment(TextAlignment::Center),
+                transform: Transform::from_translation(transform.translation.truncate().extend(1.0)),
                 ..default()
-            }));
+            },
+            OrderCountLabel { house: house_entity },
+        ));
     }
-}
\ No newline at end of file
+}
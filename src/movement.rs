@@ -0,0 +1,128 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::input::{Action, ActionState};
+use crate::stamina::{self, Stamina};
+use crate::vehicle::Vehicle;
+use crate::weather::Conditions;
+use crate::models;
+
+/// Waypoints a paperboy is walking along, handed over from the drawn `Path` on dispatch
+#[derive(Component, Debug)]
+pub struct Route {
+    pub waypoints: Vec<Vec2>,
+    // index of the waypoint currently being walked towards
+    pub next: usize,
+    // world units per second the paperboy is currently moving at
+    pub speed: f32,
+    // houses thrown at on this route, once per paper, so a miss isn't retried every frame; a
+    // paper that serves an order takes its house off again
+    pub attempted: Vec<Entity>,
+}
+
+impl Route {
+    pub fn new(waypoints: Vec<Vec2>) -> Route {
+        Route { waypoints, next: 0, speed: 0., attempted: vec![] }
+    }
+}
+
+/// What decides whether dispatching is allowed: the level, and whether the path keeps to its limits
+#[derive(SystemParam)]
+pub struct DispatchRules<'w> {
+    current: Res<'w, crate::levels::CurrentLevel>,
+    check: Res<'w, crate::path_limits::PathCheck>,
+}
+
+/// Sends the selected paperboy along the drawn path and assigns it the active houses on the way,
+/// as long as the path keeps to the level's limits
+pub fn dispatch_paperboy(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    rules: DispatchRules,
+    ui_state: Query<&crate::UIState>,
+    mut paths: Query<&mut crate::Path>,
+    selected: Query<(Entity, &Transform), crate::SelectedPaperboy>,
+    mut houses: Query<(&mut models::House, &Transform)>,
+) {
+    let DispatchRules { current, check } = rules;
+    if !actions.just_pressed(Action::Dispatch) || ui_state.single().selection_mode == crate::SelectionMode::Paused {
+        return;
+    }
+    if !current.level.unlocks(crate::levels::Mechanic::Dispatch) {
+        info!("dispatch pressed, but dispatching isn't unlocked on this level yet");
+        return;
+    }
+    let Ok((paperboy, paperboy_transform)) = selected.get_single() else { return };
+    let mut path = paths.single_mut();
+    if path.points.is_empty() {
+        info!("dispatch pressed, but no path has been drawn");
+        return;
+    }
+    if !check.is_ok() {
+        info!("dispatch pressed, but the path {}", *check);
+        return;
+    }
+
+    let mut route = vec![paperboy_transform.translation.truncate()];
+    route.extend(path.points.iter().copied());
+    for (mut house, house_transform) in &mut houses {
+        let porch = models::porch_position(house_transform.translation.truncate());
+        if house.is_active() && crate::route::distance_along(&route, porch, crate::PAPERBOY_REACH).is_some() {
+            house.assigned_to = Some(paperboy);
+        }
+    }
+
+    debug!("dispatching paperboy {:?} along {:?}", paperboy, path.points);
+    commands.entity(paperboy).insert(Route::new(path.points.clone()));
+    for entity in &path.entities {
+        commands.entity(*entity).despawn();
+    }
+    path.points.clear();
+    path.entities.clear();
+}
+
+pub fn move_paperboys(
+    mut commands: Commands,
+    time: Res<Time>,
+    conditions: Res<Conditions>,
+    mut paperboys: Query<(Entity, &mut Transform, &mut Route, &Vehicle, &mut Stamina), With<crate::Paperboy>>,
+) {
+    for (entity, mut transform, mut route, vehicle, mut stamina) in &mut paperboys {
+        let dt = time.delta_seconds();
+        // tired paperboys can't keep up their full speed, and nobody can in the rain
+        let max_speed = vehicle.max_speed * stamina::speed_factor(stamina.0) * conditions.speed_factor();
+        route.speed = (route.speed + vehicle.acceleration * dt).min(max_speed);
+        let mut budget = route.speed * dt;
+        let mut moved = 0.;
+
+        // walk through as many waypoints as this frame's movement covers
+        while let Some(target) = route.waypoints.get(route.next).copied() {
+            let position = transform.translation.truncate();
+            let distance = position.distance(target);
+            if distance > budget {
+                let step = (target - position) / distance * budget;
+                transform.translation.x += step.x;
+                transform.translation.y += step.y;
+                moved += budget;
+                break;
+            }
+            transform.translation.x = target.x;
+            transform.translation.y = target.y;
+            budget -= distance;
+            moved += distance;
+            route.next += 1;
+            // slow down for the turn onto the next leg
+            if let Some(next) = route.waypoints.get(route.next) {
+                let kept = vehicle.speed_kept_turning(target - position, *next - target);
+                route.speed *= kept;
+                budget *= kept;
+            }
+        }
+        stamina.0 = (stamina.0 - vehicle.drain(moved)).max(0.);
+
+        if route.next >= route.waypoints.len() {
+            debug!("paperboy {:?} finished its route", entity);
+            commands.entity(entity).remove::<Route>();
+        }
+    }
+}
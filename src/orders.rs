@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// shortest gap between two orders, so the delivery timer never gets a zero duration
+const MIN_ORDER_GAP: f32 = 0.05;
+// width in seconds of one bar of the offline schedule plot
+const PLOT_BUCKET: f32 = 10.0;
+
+/// One source of orders. A level's orders are the sum of all its processes, each a Poisson
+/// process whose rate, in orders per minute, may change over the shift.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum OrderProcess {
+    // (seconds into the shift, orders per minute), interpolated linearly in between
+    Steady { rate: Vec<(f32, f32)> },
+    // extra orders in a bell curve peaking at `at` seconds, falling off over `width` seconds
+    Rush { at: f32, width: f32, peak: f32 },
+    // `rate` times a minute, up to `size` houses within `radius` of each other order at once
+    Burst { rate: f32, size: u32, radius: f32 },
+}
+
+/// How new orders treat houses that are already waiting for a paper
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OrderPolicy {
+    // only houses without an outstanding order can order, the order is dropped if there are none
+    OnePerHouse,
+    // a house can wait on up to `max` papers at once, say a weekday and a Sunday edition
+    Stack { max: u32 },
+}
+
+impl OrderPolicy {
+    pub fn accepts(&self, house: &crate::models::House) -> bool {
+        match self {
+            OrderPolicy::OnePerHouse => !house.is_active(),
+            OrderPolicy::Stack { max } => house.orders.len() < *max as usize,
+        }
+    }
+}
+
+/// What happens when the order timer goes off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderEvent {
+    Single,
+    Burst { size: u32, radius: f32 },
+}
+
+impl OrderProcess {
+    /// Events per minute at `elapsed` seconds into the shift
+    pub fn rate_at(&self, elapsed: f32) -> f32 {
+        match self {
+            OrderProcess::Steady { rate } => {
+                let Some(first) = rate.first() else { return 0. };
+                if elapsed <= first.0 {
+                    return first.1;
+                }
+                for pair in rate.windows(2) {
+                    let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+                    if elapsed <= t1 {
+                        let t = if t1 > t0 { (elapsed - t0) / (t1 - t0) } else { 1. };
+                        return v0 + (v1 - v0) * t;
+                    }
+                }
+                rate[rate.len() - 1].1
+            }
+            OrderProcess::Rush { at, width, peak } => {
+                let x = (elapsed - at) / width.max(f32::EPSILON);
+                peak * (-0.5 * x * x).exp()
+            }
+            OrderProcess::Burst { rate, .. } => *rate,
+        }
+    }
+
+    /// The highest rate this process ever reaches
+    fn max_rate(&self) -> f32 {
+        match self {
+            // a piecewise linear curve peaks at one of its points
+            OrderProcess::Steady { rate } => rate.iter().map(|(_, v)| *v).fold(0., f32::max),
+            OrderProcess::Rush { peak, .. } => *peak,
+            OrderProcess::Burst { rate, .. } => *rate,
+        }
+    }
+
+    fn event(&self) -> OrderEvent {
+        match self {
+            OrderProcess::Burst { size, radius, .. } => OrderEvent::Burst { size: *size, radius: *radius },
+            _ => OrderEvent::Single,
+        }
+    }
+
+    /// Houses expected to order per event
+    fn orders_per_event(&self) -> f32 {
+        match self {
+            OrderProcess::Burst { size, .. } => *size as f32,
+            _ => 1.,
+        }
+    }
+}
+
+/// Events per minute of all processes together
+pub fn total_rate(processes: &[OrderProcess], elapsed: f32) -> f32 {
+    processes.iter().map(|process| process.rate_at(elapsed).max(0.)).sum()
+}
+
+/// Seconds from `now` until the next order event. Candidates are drawn at the highest rate the
+/// processes can reach and each is kept with probability (rate then / highest rate), which gives
+/// a Poisson process with the time-varying rate. None if there are no more orders this shift.
+pub fn next_gap(processes: &[OrderProcess], now: f32, shift_length: f32, rng: &mut impl Rng) -> Option<f32> {
+    let max_rate: f32 = processes.iter().map(|process| process.max_rate().max(0.)).sum();
+    if max_rate <= 0. {
+        return None;
+    }
+    let mut t = now;
+    loop {
+        let u: f32 = rng.gen();
+        t += -(1. - u).ln() / (max_rate / 60.);
+        if t > shift_length {
+            return None;
+        }
+        if rng.gen::<f32>() * max_rate <= total_rate(processes, t) {
+            return Some((t - now).max(MIN_ORDER_GAP));
+        }
+    }
+}
+
+/// Which process an event at `elapsed` came from, chosen in proportion to their rates then
+pub fn pick_event(processes: &[OrderProcess], elapsed: f32, rng: &mut impl Rng) -> OrderEvent {
+    let total = total_rate(processes, elapsed);
+    let mut pick = rng.gen::<f32>() * total;
+    for process in processes {
+        let rate = process.rate_at(elapsed).max(0.);
+        // strictly below, so a process with no rate right now is never picked
+        if pick < rate {
+            return process.event();
+        }
+        pick -= rate;
+    }
+    // rounding can leave a sliver past the last process
+    processes.iter().rev()
+        .find(|process| process.rate_at(elapsed) > 0.)
+        .map_or(OrderEvent::Single, OrderProcess::event)
+}
+
+/// Picks the houses for a burst: a random one out of `candidates`, then the ones closest to it
+/// within `radius`, up to `size` in total
+pub fn burst_houses(candidates: &[(Entity, Vec2)], size: u32, radius: f32, rng: &mut impl Rng) -> Vec<Entity> {
+    if candidates.is_empty() {
+        return vec![];
+    }
+    let center = candidates[rng.gen_range(0..candidates.len())].1;
+    let mut nearby: Vec<_> = candidates.iter()
+        .filter(|(_, position)| position.distance(center) <= radius)
+        .collect();
+    nearby.sort_by(|a, b| a.1.distance(center).total_cmp(&b.1.distance(center)));
+    nearby.iter().take(size as usize).map(|(entity, _)| *entity).collect()
+}
+
+/// Every event of a whole shift, as the game would generate them from a freshly seeded rng
+pub fn simulate(processes: &[OrderProcess], shift_length: f32, rng: &mut impl Rng) -> Vec<(f32, OrderEvent)> {
+    let mut events = vec![];
+    let mut now = 0.;
+    while let Some(gap) = next_gap(processes, now, shift_length, rng) {
+        now += gap;
+        events.push((now, pick_event(processes, now, rng)));
+    }
+    events
+}
+
+/// A text chart of a simulated shift for tuning levels: orders in each time bucket, next to
+/// the number the rates predict
+pub fn plot_schedule(level: &crate::levels::Level, rng: &mut impl Rng) -> String {
+    let events = simulate(&level.orders, level.shift_length, rng);
+    let orders_in = |from: f32, to: f32| -> u32 {
+        events.iter()
+            .filter(|(t, _)| *t >= from && *t < to)
+            .map(|(_, event)| match event {
+                OrderEvent::Single => 1,
+                OrderEvent::Burst { size, .. } => *size,
+            })
+            .sum()
+    };
+
+    let mut plot = format!("order schedule for {:?} over {}s\n", level.name, level.shift_length);
+    plot += "    seconds orders expected\n";
+    let mut start = 0.;
+    while start < level.shift_length {
+        let end = (start + PLOT_BUCKET).min(level.shift_length);
+        let middle = (start + end) / 2.;
+        let expected: f32 = level.orders.iter()
+            .map(|process| process.rate_at(middle).max(0.) * process.orders_per_event())
+            .sum::<f32>() * (end - start) / 60.;
+        let count = orders_in(start, end);
+        plot += &format!("{:>5.0}-{:<5.0}{:>4} {:>6.1}  {}\n", start, end, count, expected, "#".repeat(count as usize));
+        start = end;
+    }
+    plot += &format!("{} events, {} orders in total\n", events.len(), orders_in(0., f32::INFINITY));
+    plot
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::levels::CurrentLevel;
+use crate::validate;
+
+const WARNING_PATH_COLOR: Color = Color::rgb(1.0, 0.75, 0.0);
+
+/// What a level allows of a drawn path. None is no limit.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PathLimits {
+    // world units, from the first point drawn to the last
+    pub max_length: Option<f32>,
+    // points drawn
+    pub max_waypoints: Option<usize>,
+    pub no_self_crossing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathViolation {
+    SelfCrossing,
+    TooLong,
+    TooManyWaypoints,
+}
+
+impl fmt::Display for PathViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathViolation::SelfCrossing => write!(f, "crosses itself"),
+            PathViolation::TooLong => write!(f, "too long"),
+            PathViolation::TooManyWaypoints => write!(f, "has too many waypoints"),
+        }
+    }
+}
+
+impl PathLimits {
+    /// What breaks the limits about each segment of the path through `points`, if anything:
+    /// segments past the length or waypoint limit, and both segments of every crossing
+    pub fn check(&self, points: &[Vec2]) -> Vec<Option<PathViolation>> {
+        let segments: Vec<(Vec2, Vec2)> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let mut violations = vec![None; segments.len()];
+        let mut length = 0.;
+        for (index, (start, end)) in segments.iter().enumerate() {
+            length += start.distance(*end);
+            if self.max_waypoints.is_some_and(|max| index + 1 >= max) {
+                violations[index] = Some(PathViolation::TooManyWaypoints);
+            }
+            if self.max_length.is_some_and(|max| length > max) {
+                violations[index] = Some(PathViolation::TooLong);
+            }
+        }
+        if self.no_self_crossing {
+            for (i, first) in segments.iter().enumerate() {
+                for (j, second) in segments.iter().enumerate().skip(i + 1) {
+                    if validate::segments_cross(*first, *second) {
+                        violations[i] = Some(PathViolation::SelfCrossing);
+                        violations[j] = Some(PathViolation::SelfCrossing);
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// The drawn path checked against the level's limits, one entry per segment
+#[derive(Resource, Default, Debug)]
+pub struct PathCheck {
+    pub segments: Vec<Option<PathViolation>>,
+}
+
+impl PathCheck {
+    pub fn is_ok(&self) -> bool {
+        self.segments.iter().all(|violation| violation.is_none())
+    }
+
+    /// Every way the path breaks the limits, once each
+    pub fn violations(&self) -> Vec<PathViolation> {
+        let mut violations: Vec<PathViolation> = self.segments.iter().flatten().copied().collect();
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+}
+
+impl fmt::Display for PathCheck {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let violations: Vec<String> = self.violations().iter().map(|violation| violation.to_string()).collect();
+        write!(f, "{}", violations.join(", "))
+    }
+}
+
+/// Checks the path against the level's limits as it is drawn, undone or planned
+pub fn check_path(current: Res<CurrentLevel>, paths: Query<&crate::Path>, mut check: ResMut<PathCheck>) {
+    let Ok(path) = paths.get_single() else { return };
+    let segments = current.level.path_limits.check(&path.points);
+    if segments != check.segments {
+        check.segments = segments;
+        if !check.is_ok() {
+            debug!("the drawn path {}", *check);
+        }
+    }
+}
+
+/// Draws the segments that break the limits in the warning colour
+pub fn colour_path_segments(
+    check: Res<PathCheck>,
+    paths: Query<&crate::Path>,
+    mut segments: Query<&mut Sprite, With<crate::PathSegment>>,
+) {
+    let Ok(path) = paths.get_single() else { return };
+    for (index, entity) in path.entities.iter().enumerate() {
+        let Ok(mut sprite) = segments.get_mut(*entity) else { continue };
+        let color = match check.segments.get(index) {
+            Some(Some(_)) => WARNING_PATH_COLOR,
+            _ => crate::PATH_COLOR,
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
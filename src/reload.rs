@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::levels::CurrentLevel;
+use crate::map::MapData;
+use crate::movement::Route;
+use crate::subscriptions::Subscription;
+use crate::{closures, dogs, economy, graph, models, spatial, stamina, validate};
+
+// seconds of real time between looks at the map file
+const POLL_INTERVAL: f32 = 0.5;
+// a paperboy further than this from every road and from the depot is left stranded by a reload
+const STRANDED_DISTANCE: f32 = 30.0;
+
+const REPORT_FONT_SIZE: f32 = 18.0;
+const REPORT_TEXT_COLOR: Color = Color::rgb(0.8, 0., 0.);
+const REPORT_BACKGROUND_COLOR: Color = Color::rgba(1., 1., 1., 0.9);
+
+/// The current level's map file, and when it was last changed, so edits to it can be picked up
+#[derive(Resource)]
+pub struct MapWatcher {
+    path: String,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl Default for MapWatcher {
+    fn default() -> MapWatcher {
+        MapWatcher {
+            path: String::new(),
+            modified: None,
+            timer: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// What is wrong with the map in play, shown on screen until a load or reload fixes it
+#[derive(Resource, Default, Debug)]
+pub struct MapReport {
+    pub problems: Vec<String>,
+}
+
+impl MapReport {
+    pub fn validated(map: &MapData) -> MapReport {
+        MapReport { problems: validate::validate_map(map).iter().map(|problem| problem.to_string()).collect() }
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+type RoadPieces = Or<(With<crate::Road>, With<closures::Barrier>)>;
+type Fixtures = Or<(With<economy::Depot>, With<stamina::RestSpot>, With<dogs::Dog>)>;
+type Paperboys = (With<crate::Paperboy>, Without<models::House>);
+
+/// Everything on the map that follows its file
+#[derive(SystemParam)]
+pub struct OnMap<'w, 's> {
+    houses: Query<'w, 's, (Entity, &'static Transform), With<models::House>>,
+    paperboys: Query<'w, 's, (Entity, &'static mut Transform), Paperboys>,
+    roads: Query<'w, 's, Entity, RoadPieces>,
+    spots: Query<'w, 's, Entity, Fixtures>,
+}
+
+/// Rebuilds the map in place when the current level's map file changes: roads, houses, the
+/// depot, rest points and dogs follow the file, while orders on houses that stayed put, paperboys
+/// still on a road and the shift carry on. A file that can't be read leaves the map as it was.
+pub fn reload_map(
+    mut commands: Commands,
+    time: Res<Time>,
+    current: Res<CurrentLevel>,
+    mut watcher: ResMut<MapWatcher>,
+    mut report: ResMut<MapReport>,
+    on_map: OnMap,
+) {
+    let OnMap { houses, mut paperboys, roads, spots } = on_map;
+    if watcher.path != current.level.map {
+        // a new level: its map was just loaded, so only changes from here on count
+        watcher.path = current.level.map.clone();
+        watcher.modified = modified(&watcher.path);
+        return;
+    }
+    // real time, so the map can be edited while the game is paused
+    if watcher.path.is_empty() || !watcher.timer.tick(time.raw_delta()).just_finished() {
+        return;
+    }
+    let now = modified(&watcher.path);
+    if now.is_none() || now == watcher.modified {
+        return;
+    }
+    watcher.modified = now;
+
+    let map = match MapData::load(Path::new(&watcher.path)) {
+        Ok(map) => map,
+        Err(e) => {
+            warn!("could not reload map {:?}, keeping the one in play: {}", watcher.path, e);
+            report.problems = vec![format!("could not reload {}: {}", watcher.path, e)];
+            return;
+        }
+    };
+    info!("map {:?} changed, rebuilding it", watcher.path);
+    *report = MapReport::validated(&map);
+    for problem in &report.problems {
+        warn!("map {:?}: {}", map.name, problem);
+    }
+
+    // barriers go up again on the new roads next frame
+    for road in &roads {
+        commands.entity(road).despawn();
+    }
+    let world = graph::GameWorld::from(&map.graph);
+    crate::spawn_roads(&mut commands, &world);
+
+    // houses are matched by where they stand; moved ones start over as new houses
+    let mut kept = vec![false; map.houses.len()];
+    for (entity, transform) in &houses {
+        let position = transform.translation.truncate();
+        match (0..map.houses.len()).find(|index| !kept[*index] && map.houses[*index] == position) {
+            Some(index) => kept[index] = true,
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for (index, position) in map.houses.iter().enumerate().filter(|(index, _)| !kept[*index]) {
+        let house = models::spawn_house(&mut commands, *position, models::House::new());
+        if let Some(spec) = current.level.subscriptions.iter().find(|spec| spec.house == index) {
+            commands.entity(house).insert(Subscription::new(spec));
+        }
+    }
+
+    for spot in &spots {
+        commands.entity(spot).despawn();
+    }
+    economy::spawn_depot(&mut commands, map.spawn);
+    dogs::spawn_dogs(&mut commands, &current.level.dogs, &map.houses);
+    for position in &map.rest_points {
+        stamina::spawn_rest_point(&mut commands, *position);
+    }
+
+    let roads = spatial::index_roads(&world);
+    for (entity, mut transform) in &mut paperboys {
+        let position = transform.translation.truncate();
+        if roads.within(position, STRANDED_DISTANCE).is_empty() && position.distance(map.spawn) > STRANDED_DISTANCE {
+            debug!("paperboy {:?} at {} is off the new roads, sending it back to the depot", entity, position);
+            transform.translation = map.spawn.extend(transform.translation.z);
+            commands.entity(entity).remove::<Route>();
+        }
+    }
+
+    commands.insert_resource(world);
+}
+
+/// The map report, in the bottom right corner
+#[derive(Component)]
+pub struct MapReportText;
+
+pub fn setup_map_report(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: REPORT_FONT_SIZE,
+                color: REPORT_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(5.0),
+                right: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        }).with_background_color(REPORT_BACKGROUND_COLOR),
+        MapReportText,
+    ));
+}
+
+pub fn update_map_report_text(
+    report: Res<MapReport>,
+    mut text: Query<(&mut Text, &mut Visibility), With<MapReportText>>,
+) {
+    if !report.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = text.get_single_mut() else { return };
+    if report.problems.is_empty() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    text.sections[0].value = format!("Map problems:\n{}", report.problems.join("\n"));
+}
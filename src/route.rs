@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+use bevy::math::*;
+
+use crate::models;
+use crate::stamina::{self, Stamina};
+use crate::vehicle::Vehicle;
+
+const PREVIEW_FONT_SIZE: f32 = 18.0;
+const PREVIEW_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+
+/// Marks the HUD text listing length and ETA of the path being drawn
+#[derive(Component)]
+pub struct RoutePreviewText;
+
+/// Total length of the polyline through `points`
+pub fn path_length(points: &[Vec2]) -> f32 {
+    points.windows(2).map(|pair| pair[0].distance(pair[1])).sum()
+}
+
+/// Distance travelled along `points` until the route first comes within `reach` of `target`,
+/// or None if the route never gets that close
+pub fn distance_along(points: &[Vec2], target: Vec2, reach: f32) -> Option<f32> {
+    let mut travelled = 0.;
+    for pair in points.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let segment = end - start;
+        let segment_len = segment.length();
+        let t = if segment_len > 0. {
+            ((target - start).dot(segment) / (segment_len * segment_len)).clamp(0., 1.)
+        } else {
+            0.
+        };
+        if start.lerp(end, t).distance(target) < reach {
+            return Some(travelled + t * segment_len);
+        }
+        travelled += segment_len;
+    }
+    None
+}
+
+/// The first `distance` units of the polyline through `points`
+pub fn truncate(points: &[Vec2], distance: f32) -> Vec<Vec2> {
+    let mut truncated = points.iter().take(1).copied().collect::<Vec<_>>();
+    let mut left = distance;
+    for pair in points.windows(2) {
+        let segment_len = pair[0].distance(pair[1]);
+        if segment_len >= left {
+            if segment_len > 0. {
+                truncated.push(pair[0].lerp(pair[1], left / segment_len));
+            }
+            break;
+        }
+        truncated.push(pair[1]);
+        left -= segment_len;
+    }
+    truncated
+}
+
+pub fn setup_route_preview(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: PREVIEW_FONT_SIZE,
+                color: PREVIEW_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                left: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+        RoutePreviewText,
+    ));
+}
+
+pub fn preview_route(
+    time: Res<Time>,
+    current: Res<crate::levels::CurrentLevel>,
+    check: Res<crate::path_limits::PathCheck>,
+    paths: Query<&crate::Path>,
+    paperboy_transform: Query<(&Transform, &Vehicle, &Stamina), crate::SelectedPaperboy>,
+    mut houses: Query<(&models::House, &Transform, &mut Sprite)>,
+    mut preview_text: Query<&mut Text, With<RoutePreviewText>>,
+) {
+    let Ok(mut text) = preview_text.get_single_mut() else { return };
+    let Ok(path) = paths.get_single() else { return };
+    let Ok((paperboy, vehicle, paperboy_stamina)) = paperboy_transform.get_single() else { return };
+
+    // the first section is the summary line, one more section is appended per house on the route
+    text.sections.truncate(1);
+    let style = text.sections[0].style.clone();
+
+    if path.points.is_empty() || !current.level.unlocks(crate::levels::Mechanic::RoutePreview) {
+        text.sections[0].value.clear();
+        for (house, _, mut sprite) in &mut houses {
+            if house.is_active() {
+                sprite.color = models::ACTIVE_HOUSE_COLOR;
+            }
+        }
+        return;
+    }
+
+    // the route starts wherever the paperboy is standing
+    let mut route = vec![paperboy.translation.truncate()];
+    route.extend(path.points.iter().copied());
+
+    let now = time.elapsed_seconds();
+    let length = path_length(&route);
+    let (eta, stamina_left) = vehicle.travel_time(&route, paperboy_stamina.0);
+    text.sections[0].value = format!(
+        "Route: {:.0} units, ETA {:.1} s, stamina {:.0} -> {:.0}{}\n",
+        length, eta, paperboy_stamina.0, stamina_left,
+        if stamina_left < stamina::TIRED_STAMINA { " - TIRED, rest on the way" } else { "" },
+    );
+    if !check.is_ok() {
+        text.sections.push(TextSection::new(
+            format!("Path {}, fix it before dispatching\n", *check),
+            TextStyle { color: models::LATE_HOUSE_COLOR, ..style.clone() },
+        ));
+    }
+
+    for (house, house_transform, mut sprite) in &mut houses {
+        let Some(deadline) = house.deadline() else { continue };
+        let house_position = house_transform.translation.truncate();
+        let porch = models::porch_position(house_position);
+        let Some(distance) = distance_along(&route, porch, crate::PAPERBOY_REACH) else {
+            sprite.color = models::ACTIVE_HOUSE_COLOR;
+            continue;
+        };
+        let (arrival, _) = vehicle.travel_time(&truncate(&route, distance), paperboy_stamina.0);
+        // the oldest order decides whether the house is served in time
+        let due_in = deadline - now;
+        let late = arrival > due_in;
+
+        sprite.color = if late { models::LATE_HOUSE_COLOR } else { models::ACTIVE_HOUSE_COLOR };
+        text.sections.push(TextSection::new(
+            format!(
+                "House ({:.0}, {:.0}) x{}: arrives in {:.1} s, due in {:.1} s{}\n",
+                house_position.x, house_position.y, house.orders.len(), arrival, due_in,
+                if late { " - LATE" } else { "" },
+            ),
+            TextStyle {
+                color: if late { models::LATE_HOUSE_COLOR } else { style.color },
+                ..style.clone()
+            },
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::FloatOrd;
+use bevy::utils::petgraph::graph::{EdgeIndex, NodeIndex};
+use bevy::utils::petgraph::visit::EdgeRef;
+
+use crate::graph::{self, EdgeKind, GameWorld};
+use crate::movement::Route;
+use crate::input::{Action, ActionState};
+use crate::levels::{CurrentLevel, Mechanic};
+use crate::spatial::{SpatialGrid, SpatialIndex};
+use crate::stamina::{self, RestSpot, Stamina};
+use crate::vehicle::Vehicle;
+use crate::{closures, models, route, subscriptions};
+
+// most houses the planner strings together in one go
+const MAX_PLANNED_STOPS: usize = 5;
+
+// how much longer an edge in a dog's territory is taken to be
+const GUARDED_COST_FACTOR: f32 = 3.0;
+
+// a node, and the node it was reached from, since turning costs depend on both
+type State = (NodeIndex, Option<NodeIndex>);
+
+pub fn node_position(world: &GameWorld, node: NodeIndex) -> Vec2 {
+    graph::world_position(&world.graph[node])
+}
+
+/// Seconds `vehicle` takes along `edge` at full speed, or None if it may not use it or it's closed.
+/// Edges a dog guards count as slower than they are, so routes go around them where they can.
+pub fn edge_cost(world: &GameWorld, edge: EdgeIndex, vehicle: &Vehicle) -> Option<f32> {
+    if world.graph[edge].closed {
+        return None;
+    }
+    if world.graph[edge].kind == EdgeKind::Footpath && !vehicle.footpaths {
+        return None;
+    }
+    let (start, end) = world.graph.edge_endpoints(edge)?;
+    let time = node_position(world, start).distance(node_position(world, end)) / vehicle.max_speed;
+    Some(if world.graph[edge].guarded { time * GUARDED_COST_FACTOR } else { time })
+}
+
+/// Fastest way from `start` to `goal` for `vehicle` as (seconds, nodes), counting the time lost
+/// slowing down for every turn. A* over (node, previous node), with straight line time as the
+/// heuristic, which never overestimates.
+pub fn find_route(world: &GameWorld, start: NodeIndex, goal: NodeIndex, vehicle: &Vehicle) -> Option<(f32, Vec<NodeIndex>)> {
+    let goal_position = node_position(world, goal);
+    let heuristic = |node: NodeIndex| node_position(world, node).distance(goal_position) / vehicle.max_speed;
+
+    let mut best: HashMap<State, f32> = HashMap::new();
+    let mut came_from: HashMap<State, State> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert((start, None), 0.);
+    open.push(Reverse((FloatOrd(heuristic(start)), FloatOrd(0.), (start, None))));
+
+    while let Some(Reverse((_, FloatOrd(cost), state))) = open.pop() {
+        let (node, previous) = state;
+        if node == goal {
+            let mut nodes = vec![node];
+            let mut current = state;
+            while let Some(before) = came_from.get(&current) {
+                nodes.push(before.0);
+                current = *before;
+            }
+            nodes.reverse();
+            return Some((cost, nodes));
+        }
+        if best.get(&state).is_some_and(|known| *known < cost) {
+            continue;
+        }
+
+        let position = node_position(world, node);
+        for edge in world.graph.edges(node) {
+            let Some(edge_cost) = edge_cost(world, edge.id(), vehicle) else { continue };
+            let next = edge.target();
+            let next_position = node_position(world, next);
+            let turn = previous.map_or(0., |previous| {
+                vehicle.turn_time(position - node_position(world, previous), next_position - position)
+            });
+            let next_cost = cost + edge_cost + turn;
+            let next_state = (next, Some(node));
+            if best.get(&next_state).is_none_or(|known| next_cost < *known) {
+                best.insert(next_state, next_cost);
+                came_from.insert(next_state, state);
+                open.push(Reverse((FloatOrd(next_cost + heuristic(next)), FloatOrd(next_cost), next_state)));
+            }
+        }
+    }
+    None
+}
+
+/// A spot on the network part way along an edge, which can be left by either end
+#[derive(Debug, Clone, Copy)]
+struct Stop {
+    position: Vec2,
+    ends: [NodeIndex; 2],
+}
+
+/// The closest point to `target` on any edge `vehicle` may use, found through `roads`, the
+/// world's edges as indexed by `spatial::index_roads`
+fn closest_stop(world: &GameWorld, roads: &SpatialGrid<EdgeIndex>, target: Vec2, vehicle: &Vehicle) -> Option<Stop> {
+    let (edge, _) = roads.nearest(target, 1, |edge| edge_cost(world, edge, vehicle).is_some()).into_iter().next()?;
+    let (start, end) = world.graph.edge_endpoints(edge)?;
+    let (a, b) = (node_position(world, start), node_position(world, end));
+    let segment = b - a;
+    let t = if segment.length_squared() > 0. {
+        ((target - a).dot(segment) / segment.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    Some(Stop { position: a.lerp(b, t), ends: [start, end] })
+}
+
+/// Fastest way between two stops as (seconds, waypoints after `from`)
+fn route_between(world: &GameWorld, from: Stop, to: Stop, vehicle: &Vehicle) -> Option<(f32, Vec<Vec2>)> {
+    let same_edge = from.ends.iter().all(|end| to.ends.contains(end));
+    if same_edge {
+        return Some((from.position.distance(to.position) / vehicle.max_speed, vec![to.position]));
+    }
+    let mut best: Option<(f32, Vec<Vec2>)> = None;
+    for leave in from.ends {
+        for arrive in to.ends {
+            let Some((cost, nodes)) = find_route(world, leave, arrive, vehicle) else { continue };
+            let cost = cost
+                + from.position.distance(node_position(world, leave)) / vehicle.max_speed
+                + to.position.distance(node_position(world, arrive)) / vehicle.max_speed;
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                let mut waypoints: Vec<Vec2> = nodes.iter().map(|node| node_position(world, *node)).collect();
+                waypoints.push(to.position);
+                best = Some((cost, waypoints));
+            }
+        }
+    }
+    best
+}
+
+/// Sent when a paperboy's route runs into a closed road, to find it a way around along the rest
+/// of it
+#[derive(Clone)]
+pub struct Reroute {
+    pub paperboy: Entity,
+    // what was left of its route
+    pub waypoints: Vec<Vec2>,
+    pub attempted: Vec<Entity>,
+}
+
+/// On a paperboy stopped by closed roads it has no way around, until one reopens
+#[derive(Component, Debug)]
+pub struct WaitingForRoad {
+    pub waypoints: Vec<Vec2>,
+    pub attempted: Vec<Entity>,
+}
+
+/// The fastest way for `vehicle` from `from` to `to` along the roads it may use: onto the nearest
+/// of them, along them, then off again to `to`. `roads` is the world's edges as indexed by
+/// `spatial::index_roads`.
+pub fn route_to(world: &GameWorld, roads: &SpatialGrid<EdgeIndex>, from: Vec2, to: Vec2, vehicle: &Vehicle) -> Option<Vec<Vec2>> {
+    let (start, end) = (closest_stop(world, roads, from, vehicle)?, closest_stop(world, roads, to, vehicle)?);
+    let (_, leg) = route_between(world, start, end, vehicle)?;
+    let mut waypoints = vec![start.position];
+    waypoints.extend(leg);
+    waypoints.push(to);
+    waypoints.dedup();
+    Some(waypoints)
+}
+
+/// `waypoints` walked from `position`, with each leg that runs into a barrier replaced by the
+/// fastest way around it, or None if one of them has no way around
+fn detour(
+    world: &GameWorld,
+    roads: &SpatialGrid<EdgeIndex>,
+    position: Vec2,
+    waypoints: &[Vec2],
+    barriers: &[Vec2],
+    vehicle: &Vehicle,
+) -> Option<Vec<Vec2>> {
+    let mut detoured = vec![];
+    let mut from = position;
+    for to in waypoints.iter().copied() {
+        if closures::blocked(from, &[to], barriers) {
+            // getting onto or off the roads can still cut across a barrier
+            let around = route_to(world, roads, from, to, vehicle)
+                .filter(|around| !closures::blocked(from, around, barriers))?;
+            detoured.extend(around);
+        } else {
+            detoured.push(to);
+        }
+        from = to;
+    }
+    detoured.dedup();
+    Some(detoured)
+}
+
+/// The barriers standing now, and the ones taken down since last frame
+#[derive(SystemParam)]
+pub struct Barriers<'w, 's> {
+    standing: Query<'w, 's, &'static Transform, With<closures::Barrier>>,
+    taken_down: RemovedComponents<'w, 's, closures::Barrier>,
+}
+
+/// Sends stopped paperboys on along the rest of their routes, around the closed roads. One with
+/// no way around waits where it is, and tries again whenever a road reopens.
+pub fn reroute_paperboys(
+    mut commands: Commands,
+    mut requests: EventReader<Reroute>,
+    map: Res<GameWorld>,
+    index: Res<SpatialIndex>,
+    barriers: Barriers,
+    paperboys: Query<(&Transform, &Vehicle), With<crate::Paperboy>>,
+    waiting: Query<(Entity, &WaitingForRoad, Option<&Route>)>,
+) {
+    let Barriers { standing: barriers, taken_down: mut reopened } = barriers;
+    let mut requests: Vec<Reroute> = requests.iter().cloned().collect();
+    if reopened.iter().count() > 0 {
+        for (paperboy, wait, route) in &waiting {
+            commands.entity(paperboy).remove::<WaitingForRoad>();
+            // sent somewhere else in the meantime
+            if route.is_none() {
+                requests.push(Reroute { paperboy, waypoints: wait.waypoints.clone(), attempted: wait.attempted.clone() });
+            }
+        }
+    }
+
+    let barriers: Vec<Vec2> = barriers.iter().map(|transform| transform.translation.truncate()).collect();
+    for request in requests {
+        let Ok((transform, vehicle)) = paperboys.get(request.paperboy) else { continue };
+        let position = transform.translation.truncate();
+        let Some(waypoints) = detour(&map, &index.roads, position, &request.waypoints, &barriers, vehicle) else {
+            info!("paperboy {:?} has no way around the closed road, waiting for it to reopen", request.paperboy);
+            commands.entity(request.paperboy).insert(WaitingForRoad { waypoints: request.waypoints, attempted: request.attempted });
+            continue;
+        };
+        debug!("rerouting paperboy {:?} through {:?}", request.paperboy, waypoints);
+        commands.entity(request.paperboy).insert(Route { attempted: request.attempted, ..Route::new(waypoints) });
+    }
+}
+
+/// Greedily strings together the active houses nobody has been sent to, nearest by travel time
+/// first, and appends the roads to them to the path being drawn for the selected paperboy. When
+/// the next house would leave the paperboy tired, the path heads for the nearest rest spot instead.
+/// It stops short of anything that would take the path past the level's limits.
+/// Where a planned route can go, houses waiting on a paper and rest spots, and the roads there
+#[derive(SystemParam)]
+pub struct Destinations<'w, 's> {
+    map: Res<'w, GameWorld>,
+    index: Res<'w, SpatialIndex>,
+    rest_spots: Query<'w, 's, &'static Transform, With<RestSpot>>,
+    houses: Query<'w, 's, (&'static models::House, &'static Transform, Option<&'static subscriptions::Subscription>)>,
+}
+
+pub fn plan_route(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    current: Res<CurrentLevel>,
+    ui_state: Query<&crate::UIState>,
+    selected: Query<(&Transform, &Vehicle, &Stamina), crate::SelectedPaperboy>,
+    destinations: Destinations,
+    mut paths: Query<&mut crate::Path>,
+) {
+    let Destinations { map, index, rest_spots, houses } = destinations;
+    if !actions.just_pressed(Action::PlanRoute) || ui_state.single().selection_mode == crate::SelectionMode::Paused {
+        return;
+    }
+    if !current.level.unlocks(Mechanic::AutoPlan) {
+        info!("plan pressed, but route planning isn't unlocked on this level yet");
+        return;
+    }
+    let Ok((transform, vehicle, paperboy_stamina)) = selected.get_single() else { return };
+    let Ok(mut path) = paths.get_single_mut() else { return };
+
+    let start = path.points.last().copied().unwrap_or(transform.translation.truncate());
+    let Some(mut stop) = closest_stop(&map, &index.roads, start, vehicle) else {
+        info!("plan pressed, but there are no roads this paperboy can use");
+        return;
+    };
+    // whether the drawn path keeps to the level's limits with `waypoints` added on
+    let fits = |waypoints: &[Vec2]| {
+        let mut points = path.points.clone();
+        points.extend(waypoints);
+        points.dedup();
+        current.level.path_limits.check(&points).iter().all(Option::is_none)
+    };
+    let mut waypoints = vec![stop.position];
+    if !fits(&waypoints) {
+        info!("plan pressed, but the path can't go any further within this level's limits");
+        return;
+    }
+    let mut targets: Vec<Stop> = houses.iter()
+        .filter(|(house, _, _)| house.is_active() && house.assigned_to.is_none())
+        .filter_map(|(_, house_transform, subscription)| {
+            let target = subscriptions::aim_point(house_transform.translation.truncate(), subscription);
+            closest_stop(&map, &index.roads, target, vehicle)
+        })
+        .collect();
+    let rests: Vec<Stop> = rest_spots.iter()
+        .filter_map(|rest| closest_stop(&map, &index.roads, rest.translation.truncate(), vehicle))
+        .collect();
+
+    // what's already drawn has to be walked first
+    let mut drawn = vec![transform.translation.truncate()];
+    drawn.extend(path.points.iter().copied());
+    let mut stamina_left = paperboy_stamina.0 - vehicle.drain(route::path_length(&drawn));
+    for _ in 0..MAX_PLANNED_STOPS {
+        let Some((index, (_, leg))) = targets.iter()
+            .enumerate()
+            .filter_map(|(index, target)| route_between(&map, stop, *target, vehicle).map(|leg| (index, leg)))
+            .min_by_key(|(_, (cost, _))| FloatOrd(*cost))
+        else {
+            break;
+        };
+        let mut leg_points = vec![stop.position];
+        leg_points.extend(leg.iter().copied());
+        let drain = vehicle.drain(route::path_length(&leg_points));
+        if stamina_left - drain < stamina::TIRED_STAMINA {
+            let rest = rests.iter()
+                .filter_map(|rest| route_between(&map, stop, *rest, vehicle))
+                .min_by_key(|(cost, _)| FloatOrd(*cost));
+            if let Some((_, rest_leg)) = rest {
+                let with_rest = [waypoints.as_slice(), rest_leg.as_slice()].concat();
+                if fits(&with_rest) {
+                    debug!("paperboy would be worn out by the next house, heading for a rest first");
+                    waypoints = with_rest;
+                }
+            }
+            break;
+        }
+        let with_leg = [waypoints.as_slice(), leg.as_slice()].concat();
+        if !fits(&with_leg) {
+            debug!("the next house would take the path past this level's limits");
+            break;
+        }
+        stamina_left -= drain;
+        waypoints = with_leg;
+        stop = targets.swap_remove(index);
+    }
+    waypoints.dedup();
+
+    debug!("planned a route through {:?}", waypoints);
+    let mut previous = path.points.last().copied();
+    for point in waypoints {
+        if let Some(previous) = previous {
+            let segment = crate::spawn_path_segment(&mut commands, previous, point);
+            path.entities.push(segment);
+        }
+        path.points.push(point);
+        previous = Some(point);
+    }
+}
@@ -0,0 +1,526 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::economy;
+use crate::input::{Action, ActionState};
+use crate::movement::Route;
+use crate::throwing::{self, AutoThrow, Paper};
+use crate::scoring::Score;
+use crate::shift::Shift;
+use crate::stamina::{self, Stamina};
+use crate::subscriptions::{Preference, Subscription};
+use crate::{graph, models};
+
+pub const SAVE_PATH: &str = "saves/quicksave.ron";
+
+/// Version written into new saves. Bump it whenever `SaveState` changes shape, and add a
+/// migration from the previous version to `MIGRATIONS`.
+pub const SAVE_VERSION: u32 = 8;
+
+/// Upgrades the untyped state of a save from version `i + 1` to version `i + 2`, so that any
+/// older save can be brought up to date by running the migrations after its version in order
+type Migration = fn(ron::Value) -> Result<ron::Value, String>;
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+];
+
+fn add_field(state: ron::Value, name: &str, value: ron::Value) -> Result<ron::Value, String> {
+    let ron::Value::Map(mut fields) = state else {
+        return Err("save state is not a struct".to_string());
+    };
+    fields.insert(ron::Value::String(name.to_string()), value);
+    Ok(ron::Value::Map(fields))
+}
+
+// version 2 added shifts and lateness tracking; older saves start with an untouched shift
+fn migrate_v1_to_v2(state: ron::Value) -> Result<ron::Value, String> {
+    let zero = || ron::Value::Number(ron::value::Number::new(0.));
+    let state = add_field(state, "late_deliveries", ron::Value::Number(ron::value::Number::new(0)))?;
+    let state = add_field(state, "lateness", zero())?;
+    add_field(state, "shift_elapsed", zero())
+}
+
+// version 3 added the campaign; older saves were all made on what is now the first level
+fn migrate_v2_to_v3(state: ron::Value) -> Result<ron::Value, String> {
+    add_field(state, "level", ron::Value::Number(ron::value::Number::new(0)))
+}
+
+/// Runs `migrate` on every struct in the list field `name`, such as the saved houses
+fn migrate_list(
+    state: ron::Value,
+    name: &str,
+    migrate: impl Fn(ron::Map) -> Result<ron::Value, String>,
+) -> Result<ron::Value, String> {
+    let ron::Value::Map(mut fields) = state else {
+        return Err("save state is not a struct".to_string());
+    };
+    let key = ron::Value::String(name.to_string());
+    let Some(ron::Value::Seq(items)) = fields.remove(&key) else {
+        return Err(format!("save state has no list of {}", name));
+    };
+    let items = items.into_iter().map(|item| match item {
+        ron::Value::Map(item) => migrate(item),
+        _ => Err(format!("saved {} are not structs", name)),
+    }).collect::<Result<Vec<_>, String>>()?;
+    fields.insert(key, ron::Value::Seq(items));
+    Ok(ron::Value::Map(fields))
+}
+
+// version 4 let houses wait on several orders; an active house had exactly one
+fn migrate_v3_to_v4(state: ron::Value) -> Result<ron::Value, String> {
+    migrate_list(state, "houses", |mut house| {
+        let active = house.remove(&ron::Value::String("active".to_string()));
+        let due_in = house.remove(&ron::Value::String("due_in".to_string()));
+        let orders = match (active, due_in) {
+            (Some(ron::Value::Bool(true)), Some(due_in)) => vec![due_in],
+            _ => vec![],
+        };
+        add_field(ron::Value::Map(house), "orders", ron::Value::Seq(orders))
+    })
+}
+
+// version 5 added subscriptions; every older order was a one-off
+fn migrate_v4_to_v5(state: ron::Value) -> Result<ron::Value, String> {
+    migrate_list(state, "houses", |mut house| {
+        let Some(ron::Value::Seq(orders)) = house.remove(&ron::Value::String("orders".to_string())) else {
+            return Err("saved house has no list of orders".to_string());
+        };
+        let orders = orders.into_iter().map(|due_in| {
+            let order = add_field(ron::Value::Map(ron::Map::new()), "due_in", due_in)?;
+            add_field(order, "subscription", ron::Value::Bool(false))
+        }).collect::<Result<Vec<_>, String>>()?;
+        let house = add_field(ron::Value::Map(house), "orders", ron::Value::Seq(orders))?;
+        add_field(house, "subscription", ron::Value::Option(None))
+    })
+}
+
+// version 6 added money and limited bags; nothing was earned before, and bags start full
+fn migrate_v5_to_v6(state: ron::Value) -> Result<ron::Value, String> {
+    let zero = || ron::Value::Number(ron::value::Number::new(0));
+    let state = add_field(state, "wages", zero())?;
+    let state = add_field(state, "tips", zero())?;
+    migrate_list(state, "paperboys", |paperboy| {
+        let papers = ron::Value::Number(ron::value::Number::new(economy::BASE_BAG_CAPACITY as i64));
+        add_field(ron::Value::Map(paperboy), "papers", papers)
+    })
+}
+
+// version 7 added footpaths to the graph; older maps only had roads
+fn migrate_v6_to_v7(state: ron::Value) -> Result<ron::Value, String> {
+    let ron::Value::Map(mut fields) = state else {
+        return Err("save state is not a struct".to_string());
+    };
+    let graph_key = ron::Value::String("graph".to_string());
+    let Some(graph) = fields.remove(&graph_key) else {
+        return Err("save state has no graph".to_string());
+    };
+    fields.insert(graph_key, add_field(graph, "footpaths", ron::Value::Seq(vec![]))?);
+    Ok(ron::Value::Map(fields))
+}
+
+// version 8 added stamina; everyone was fresh before
+fn migrate_v7_to_v8(state: ron::Value) -> Result<ron::Value, String> {
+    migrate_list(state, "paperboys", |paperboy| {
+        let stamina = ron::Value::Number(ron::value::Number::new(stamina::MAX_STAMINA as f64));
+        add_field(ron::Value::Map(paperboy), "stamina", stamina)
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedOrder {
+    // seconds until the order is due, since the game clock restarts on load
+    pub due_in: f32,
+    pub subscription: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedSubscription {
+    pub window: (f32, f32),
+    // whether the paper goes in the mailbox rather than on the porch
+    pub mailbox: bool,
+    pub satisfaction: f32,
+    pub ordered: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedHouse {
+    pub position: Vec2,
+    // oldest first
+    pub orders: Vec<SavedOrder>,
+    pub subscription: Option<SavedSubscription>,
+    // index into `SaveState::paperboys`
+    pub assigned_to: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedRoute {
+    pub waypoints: Vec<Vec2>,
+    pub next: usize,
+    pub speed: f32,
+    // indices into `SaveState::houses`
+    pub attempted: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedPaperboy {
+    pub position: Vec2,
+    pub selected: bool,
+    pub route: Option<SavedRoute>,
+    // papers left in the bag
+    pub papers: u32,
+    pub stamina: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedPaper {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Everything needed to put a shift back exactly as it was. Papers lying on the ground are
+/// left out as they no longer affect the game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveState {
+    // index into the campaign's levels
+    pub level: usize,
+    pub graph: graph::GraphData,
+    pub houses: Vec<SavedHouse>,
+    pub paperboys: Vec<SavedPaperboy>,
+    pub papers: Vec<SavedPaper>,
+    // waypoints of the path being drawn
+    pub path: Vec<Vec2>,
+    pub delivery_timer_duration: f32,
+    pub delivery_timer_elapsed: f32,
+    pub score: i32,
+    pub deliveries: u32,
+    pub late_deliveries: u32,
+    pub lateness: f32,
+    pub wages: u32,
+    pub tips: u32,
+    pub shift_elapsed: f32,
+    pub auto_throw: bool,
+    pub rng_seed: u32,
+    // position in the RNG's stream
+    #[serde(deserialize_with = "deserialize_word_pos")]
+    pub rng_word_pos: u128,
+}
+
+#[derive(Serialize)]
+struct SaveFileOut<'a> {
+    version: u32,
+    state: &'a SaveState,
+}
+
+#[derive(Deserialize)]
+struct SaveFileIn {
+    state: ron::Value,
+}
+
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct SaveFileCurrent {
+    state: SaveState,
+}
+
+pub fn write_save(path: &Path, state: &SaveState) -> Result<(), String> {
+    let file = SaveFileOut { version: SAVE_VERSION, state };
+    let contents = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+pub fn parse_save(contents: &str) -> Result<SaveState, String> {
+    let SaveVersion { version } = ron::from_str(contents).map_err(|e| e.to_string())?;
+    if version == 0 || version > SAVE_VERSION {
+        return Err(format!("save version {} is not supported, expected 1 to {}", version, SAVE_VERSION));
+    }
+    // read straight into the state when there is nothing to migrate, so the RNG position is exact
+    if version == SAVE_VERSION {
+        let file: SaveFileCurrent = ron::from_str(contents).map_err(|e| e.to_string())?;
+        return Ok(file.state);
+    }
+    let file: SaveFileIn = ron::from_str(contents).map_err(|e| e.to_string())?;
+    let mut state = file.state;
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        state = migration(state)?;
+    }
+    state.into_rust().map_err(|e| e.to_string())
+}
+
+/// Reads the RNG position as written, or as a `ron::Value` holds it after migrating, which
+/// can't be read as a u128 directly
+fn deserialize_word_pos<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    struct WordPos;
+
+    impl<'de> Visitor<'de> for WordPos {
+        type Value = u128;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a position in the RNG's stream")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<u128, E> {
+            Ok(v.into())
+        }
+
+        // `ron::Value` keeps every integer as an i64
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<u128, E> {
+            Ok((v as u64).into())
+        }
+
+        fn visit_u128<E: de::Error>(self, v: u128) -> Result<u128, E> {
+            Ok(v)
+        }
+    }
+
+    deserializer.deserialize_any(WordPos)
+}
+
+pub fn read_save(path: &Path) -> Result<SaveState, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    parse_save(&contents)
+}
+
+/// The resources a save keeps besides the map and what's on it
+#[derive(SystemParam)]
+pub struct Run<'w> {
+    current: Res<'w, crate::levels::CurrentLevel>,
+    timer: Res<'w, crate::NewDeliveryTimer>,
+    score: Res<'w, Score>,
+    shift: Res<'w, Shift>,
+    auto_throw: Res<'w, AutoThrow>,
+    game_rng: Res<'w, crate::GameRng>,
+}
+
+/// `Run`, to be overwritten by a load
+#[derive(SystemParam)]
+pub struct RunMut<'w> {
+    current: ResMut<'w, crate::levels::CurrentLevel>,
+    timer: ResMut<'w, crate::NewDeliveryTimer>,
+    score: ResMut<'w, Score>,
+    shift: ResMut<'w, Shift>,
+    auto_throw: ResMut<'w, AutoThrow>,
+    game_rng: ResMut<'w, crate::GameRng>,
+}
+
+type SavedPaperboyQuery<'a> = (
+    Entity,
+    &'a Transform,
+    Option<&'a Route>,
+    Option<&'a crate::Selected>,
+    Option<&'a economy::Bag>,
+    Option<&'a Stamina>,
+);
+
+/// Everything on the map a save keeps
+#[derive(SystemParam)]
+pub struct Saved<'w, 's> {
+    houses: Query<'w, 's, (Entity, &'static models::House, &'static Transform, Option<&'static Subscription>)>,
+    paperboys: Query<'w, 's, SavedPaperboyQuery<'static>, With<crate::Paperboy>>,
+    papers: Query<'w, 's, (&'static Paper, &'static Transform)>,
+    paths: Query<'w, 's, &'static crate::Path>,
+}
+
+pub fn save_game(
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    map: Res<graph::GameWorld>,
+    run: Run,
+    saved: Saved,
+) {
+    let Run { current, timer, score, shift, auto_throw, game_rng } = run;
+    let Saved { houses, paperboys, papers, paths } = saved;
+    if !actions.just_pressed(Action::QuickSave) {
+        return;
+    }
+
+    let house_entities: Vec<Entity> = houses.iter().map(|(entity, _, _, _)| entity).collect();
+    let paperboy_entities: Vec<Entity> = paperboys.iter().map(|(entity, _, _, _, _, _)| entity).collect();
+    let index_of = |entities: &[Entity], entity: Entity| entities.iter().position(|e| *e == entity);
+
+    let state = SaveState {
+        level: current.index,
+        graph: graph::GraphData::from(&*map),
+        houses: houses.iter().map(|(_, house, transform, subscription)| SavedHouse {
+            position: transform.translation.truncate(),
+            orders: house.orders.iter().map(|order| SavedOrder {
+                due_in: order.deadline - time.elapsed_seconds(),
+                subscription: order.subscription,
+            }).collect(),
+            subscription: subscription.map(|subscription| SavedSubscription {
+                window: subscription.window,
+                mailbox: subscription.preference == Preference::Mailbox,
+                satisfaction: subscription.satisfaction,
+                ordered: subscription.ordered,
+            }),
+            assigned_to: house.assigned_to.and_then(|paperboy| index_of(&paperboy_entities, paperboy)),
+        }).collect(),
+        paperboys: paperboys.iter().map(|(_, transform, route, selected, bag, stamina)| SavedPaperboy {
+            position: transform.translation.truncate(),
+            selected: selected.is_some(),
+            route: route.map(|route| SavedRoute {
+                waypoints: route.waypoints.clone(),
+                next: route.next,
+                speed: route.speed,
+                attempted: route.attempted.iter().filter_map(|house| index_of(&house_entities, *house)).collect(),
+            }),
+            papers: bag.map_or(0, |bag| bag.papers),
+            stamina: stamina.map_or(stamina::MAX_STAMINA, |stamina| stamina.0),
+        }).collect(),
+        papers: papers.iter().map(|(paper, transform)| SavedPaper {
+            position: transform.translation.truncate(),
+            velocity: paper.velocity,
+        }).collect(),
+        path: paths.get_single().map(|path| path.points.clone()).unwrap_or_default(),
+        delivery_timer_duration: timer.0.duration().as_secs_f32(),
+        delivery_timer_elapsed: timer.0.elapsed_secs(),
+        score: score.points,
+        deliveries: score.deliveries,
+        late_deliveries: score.late_deliveries,
+        lateness: score.lateness,
+        wages: score.wages,
+        tips: score.tips,
+        shift_elapsed: shift.timer.elapsed_secs(),
+        auto_throw: auto_throw.0,
+        rng_seed: game_rng.seed,
+        rng_word_pos: game_rng.rng.get_word_pos(),
+    };
+
+    match write_save(Path::new(SAVE_PATH), &state) {
+        Ok(()) => info!("saved game to {:?}", SAVE_PATH),
+        Err(e) => error!("could not save game to {:?}: {}", SAVE_PATH, e),
+    }
+}
+
+pub fn load_game(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    campaign: Res<crate::levels::Campaign>,
+    profile: Res<economy::Profile>,
+    run: RunMut,
+    replaced: crate::levels::Replaced,
+) {
+    let RunMut { mut current, mut timer, mut score, mut shift, mut auto_throw, mut game_rng } = run;
+    let crate::levels::Replaced { old_entities, mut paths } = replaced;
+    if !actions.just_pressed(Action::QuickLoad) {
+        return;
+    }
+
+    let state = match read_save(Path::new(SAVE_PATH)) {
+        Ok(state) => state,
+        Err(e) => {
+            warn!("could not load game from {:?}: {}", SAVE_PATH, e);
+            return;
+        }
+    };
+
+    let Some(level) = campaign.levels.get(state.level) else {
+        warn!("could not load game from {:?}: the campaign has no level {}", SAVE_PATH, state.level);
+        return;
+    };
+    *current = crate::levels::CurrentLevel { index: state.level, level: level.clone() };
+
+    for entity in &old_entities {
+        commands.entity(entity).despawn();
+    }
+
+    let map = graph::GameWorld::from(&state.graph);
+    crate::spawn_roads(&mut commands, &map);
+    commands.insert_resource(map);
+    let level_map = current.level.load_map();
+    economy::spawn_depot(&mut commands, level_map.spawn);
+    // dogs aren't saved, they start the game at home
+    crate::dogs::spawn_dogs(&mut commands, &current.level.dogs, &level_map.houses);
+    for position in &level_map.rest_points {
+        stamina::spawn_rest_point(&mut commands, *position);
+    }
+
+    let paperboys: Vec<Entity> = state.paperboys.iter().map(|saved| {
+        let entity = crate::spawn_paperboy(&mut commands, saved.position);
+        if saved.selected {
+            commands.entity(entity).insert(crate::Selected);
+        }
+        commands.entity(entity).insert(economy::Bag { papers: saved.papers, capacity: profile.bag_capacity() });
+        commands.entity(entity).insert(Stamina(saved.stamina));
+        entity
+    }).collect();
+
+    let houses: Vec<Entity> = state.houses.iter().map(|saved| {
+        let house = models::spawn_house(&mut commands, saved.position, models::House {
+            orders: saved.orders.iter()
+                .map(|order| models::Order {
+                    deadline: time.elapsed_seconds() + order.due_in,
+                    subscription: order.subscription,
+                })
+                .collect(),
+            assigned_to: saved.assigned_to.and_then(|index| paperboys.get(index).copied()),
+        });
+        if let Some(subscription) = &saved.subscription {
+            commands.entity(house).insert(Subscription {
+                window: subscription.window,
+                preference: if subscription.mailbox { Preference::Mailbox } else { Preference::Porch },
+                satisfaction: subscription.satisfaction,
+                ordered: subscription.ordered,
+            });
+        }
+        house
+    }).collect();
+
+    for (saved, entity) in state.paperboys.iter().zip(&paperboys) {
+        if let Some(saved_route) = &saved.route {
+            let mut route = Route::new(saved_route.waypoints.clone());
+            route.next = saved_route.next;
+            route.speed = saved_route.speed;
+            route.attempted = saved_route.attempted.iter().filter_map(|index| houses.get(*index).copied()).collect();
+            commands.entity(*entity).insert(route);
+        }
+    }
+
+    for paper in &state.papers {
+        throwing::spawn_paper(&mut commands, paper.position, paper.velocity);
+    }
+
+    if let Ok(mut path) = paths.get_single_mut() {
+        path.entities = state.path.windows(2)
+            .map(|pair| crate::spawn_path_segment(&mut commands, pair[0], pair[1]))
+            .collect();
+        path.points = state.path.clone();
+    }
+
+    timer.0 = Timer::from_seconds(state.delivery_timer_duration, TimerMode::Repeating);
+    timer.0.set_elapsed(Duration::from_secs_f32(state.delivery_timer_elapsed));
+    score.points = state.score;
+    score.deliveries = state.deliveries;
+    score.late_deliveries = state.late_deliveries;
+    score.lateness = state.lateness;
+    score.wages = state.wages;
+    score.tips = state.tips;
+    shift.timer = Timer::from_seconds(current.level.shift_length, TimerMode::Once);
+    shift.timer.set_elapsed(Duration::from_secs_f32(state.shift_elapsed));
+    auto_throw.0 = state.auto_throw;
+    *game_rng = crate::GameRng::from_seed(state.rng_seed);
+    game_rng.rng.set_word_pos(state.rng_word_pos);
+
+    info!("loaded game from {:?}", SAVE_PATH);
+}
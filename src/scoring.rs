@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::models;
+
+// how far from a house's center its lawn stretches
+const LAWN_RADIUS: f32 = 60.;
+
+const PORCH_POINTS: i32 = 10;
+const LAWN_POINTS: i32 = 3;
+const ROAD_POINTS: i32 = -5;
+
+const SCORE_FONT_SIZE: f32 = 22.0;
+const SCORE_TEXT_COLOR: Color = Color::rgb(0., 0., 0.);
+
+/// Where a paper came to rest
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropZone {
+    // on the porch of the given house: delivered, full points
+    Porch(Entity),
+    // in the mailbox of the given house, for subscribers who want it there: full points
+    Mailbox(Entity),
+    // on the lawn of the given house: delivered, but the customer has to go fetch it
+    Lawn(Entity),
+    // on the road, where it gets run over
+    Road,
+    // somewhere nobody will ever find it
+    Nowhere,
+}
+
+impl DropZone {
+    pub fn points(&self) -> i32 {
+        match self {
+            DropZone::Porch(_) | DropZone::Mailbox(_) => PORCH_POINTS,
+            DropZone::Lawn(_) => LAWN_POINTS,
+            DropZone::Road => ROAD_POINTS,
+            DropZone::Nowhere => 0,
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Score {
+    pub points: i32,
+    pub deliveries: u32,
+    // deliveries that landed after their deadline
+    pub late_deliveries: u32,
+    // total seconds by which late deliveries missed their deadline
+    pub lateness: f32,
+    // dollars earned this shift, paid into the profile when it ends
+    pub wages: u32,
+    pub tips: u32,
+}
+
+#[derive(Component)]
+pub struct ScoreText;
+
+fn contains(center: Vec2, size: Vec2, point: Vec2) -> bool {
+    let half = size.abs() / 2.;
+    (point - center).abs().cmple(half).all()
+}
+
+/// Works out which zone a paper resting at `landing` is in, given house centers, mailbox
+/// positions by house and road rectangles as (center, size). Mailboxes and porches win over
+/// roads, which win over lawns, so a paper on the pavement in front of a house counts as on
+/// the porch.
+pub fn classify_landing(
+    landing: Vec2,
+    houses: &[(Entity, Vec2)],
+    mailboxes: &[(Entity, Vec2)],
+    roads: &[(Vec2, Vec2)],
+) -> DropZone {
+    if let Some((entity, _)) = mailboxes.iter().find(|(_, position)| contains(*position, crate::subscriptions::MAILBOX_SIZE, landing)) {
+        return DropZone::Mailbox(*entity);
+    }
+    let porch_size = Vec2::new(models::HOUSE_SIZE.x, models::PORCH_DEPTH);
+    if let Some((entity, _)) = houses.iter().find(|(_, position)| contains(models::porch_position(*position), porch_size, landing)) {
+        return DropZone::Porch(*entity);
+    }
+    if roads.iter().any(|(center, size)| contains(*center, *size, landing)) {
+        return DropZone::Road;
+    }
+    houses.iter()
+        .map(|(entity, position)| (*entity, position.distance(landing)))
+        .filter(|(_, distance)| *distance < LAWN_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| DropZone::Lawn(entity))
+        .unwrap_or(DropZone::Nowhere)
+}
+
+pub fn setup_score(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(crate::FONT_PATH),
+                font_size: SCORE_FONT_SIZE,
+                color: SCORE_TEXT_COLOR,
+            },
+        ).with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(5.0),
+                left: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+        ScoreText,
+    ));
+}
+
+pub fn update_score_text(
+    score: Res<Score>,
+    shift: Res<crate::shift::Shift>,
+    profile: Res<crate::economy::Profile>,
+    houses: Query<&models::House>,
+    bags: Query<&crate::economy::Bag, With<crate::Selected>>,
+    stamina: Query<&crate::stamina::Stamina, With<crate::Selected>>,
+    mut text: Query<&mut Text, With<ScoreText>>,
+) {
+    if let Ok(mut text) = text.get_single_mut() {
+        let waiting: usize = houses.iter().map(|house| house.orders.len()).sum();
+        let bag = bags.get_single().map(|bag| format!("{}/{}", bag.papers, bag.capacity)).unwrap_or_default();
+        let stamina = stamina.get_single().map(|stamina| format!("{:.0}", stamina.0)).unwrap_or_default();
+        text.sections[0].value = format!(
+            "Score: {}   Deliveries: {} ({} late)   Orders waiting: {}   Bag: {}   Stamina: {}   ${} (+${} today)   Shift ends in {:.0} s",
+            score.points, score.deliveries, score.late_deliveries, waiting, bag, stamina,
+            profile.money, score.wages + score.tips, shift.remaining_secs(),
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::{models, orders};
+use crate::movement::Route;
+use crate::scoring::Score;
+use crate::throwing::{LandedPaper, Paper};
+
+// seconds in one shift
+pub const SHIFT_LENGTH: f32 = 180.0;
+
+/// The current working shift; when it runs out the score goes on the leaderboard
+#[derive(Resource)]
+pub struct Shift {
+    pub timer: Timer,
+}
+
+impl Default for Shift {
+    fn default() -> Shift {
+        Shift { timer: Timer::from_seconds(SHIFT_LENGTH, TimerMode::Once) }
+    }
+}
+
+impl Shift {
+    pub fn is_over(&self) -> bool {
+        self.timer.finished()
+    }
+
+    pub fn remaining_secs(&self) -> f32 {
+        self.timer.remaining_secs()
+    }
+}
+
+/// Sent once when a shift's time runs out
+pub struct ShiftEnded;
+
+/// Sent to reset the map and begin another shift
+pub struct StartShift;
+
+pub fn tick_shift(
+    mut time: ResMut<Time>,
+    mut shift: ResMut<Shift>,
+    mut ui_states: Query<&mut crate::UIState>,
+    mut shift_ended: EventWriter<ShiftEnded>,
+) {
+    if !shift.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    info!("shift is over");
+    // freeze everything until the next shift starts
+    time.pause();
+    for mut ui_state in &mut ui_states {
+        if ui_state.selection_mode != crate::SelectionMode::Paused {
+            ui_state.paused_from = ui_state.selection_mode;
+            ui_state.selection_mode = crate::SelectionMode::Paused;
+        }
+    }
+    shift_ended.send(ShiftEnded);
+}
+
+/// The resources a new shift starts over
+#[derive(SystemParam)]
+pub struct ShiftProgress<'w> {
+    shift: ResMut<'w, Shift>,
+    score: ResMut<'w, Score>,
+    timer: ResMut<'w, crate::NewDeliveryTimer>,
+    game_rng: ResMut<'w, crate::GameRng>,
+}
+
+type AnyPaper = Or<(With<Paper>, With<LandedPaper>)>;
+
+/// What's left on the map from the last shift
+#[derive(SystemParam)]
+pub struct LastShift<'w, 's> {
+    houses: Query<'w, 's, (&'static mut models::House, &'static mut Sprite)>,
+    paperboys: Query<'w, 's, Entity, With<Route>>,
+    papers: Query<'w, 's, Entity, AnyPaper>,
+}
+
+/// Puts the map back to the start of a shift, keeping paperboys where they are standing
+pub fn start_new_shift(
+    mut commands: Commands,
+    mut start_shift: EventReader<StartShift>,
+    mut time: ResMut<Time>,
+    progress: ShiftProgress,
+    current: Res<crate::levels::CurrentLevel>,
+    mut ui_states: Query<&mut crate::UIState>,
+    last_shift: LastShift,
+) {
+    let ShiftProgress { mut shift, mut score, mut timer, mut game_rng } = progress;
+    let LastShift { mut houses, paperboys, papers } = last_shift;
+    if start_shift.iter().count() == 0 {
+        return;
+    }
+
+    shift.timer = Timer::from_seconds(current.level.shift_length, TimerMode::Once);
+    *score = Score::default();
+    // the same seed again, so shifts on the same map and seed are comparable
+    *game_rng = crate::GameRng::from_seed(game_rng.seed);
+    let level = &current.level;
+    let first_order = orders::next_gap(&level.orders, 0., level.shift_length, &mut game_rng.rng);
+    timer.0 = Timer::from_seconds(first_order.unwrap_or(level.shift_length), TimerMode::Repeating);
+    for (mut house, mut sprite) in &mut houses {
+        models::clear_orders(&mut house, &mut sprite);
+    }
+    for paperboy in &paperboys {
+        commands.entity(paperboy).remove::<Route>();
+    }
+    for paper in &papers {
+        commands.entity(paper).despawn();
+    }
+    for mut ui_state in &mut ui_states {
+        if ui_state.selection_mode == crate::SelectionMode::Paused {
+            ui_state.selection_mode = ui_state.paused_from;
+        }
+    }
+    time.unpause();
+    info!("starting a new shift");
+}
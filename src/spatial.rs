@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy::utils::FloatOrd;
+use bevy::utils::petgraph::graph::EdgeIndex;
+
+use crate::graph::{self, GameWorld};
+use crate::models;
+
+// world units on a side; a few houses to a cell
+pub const CELL_SIZE: f32 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Point(Vec2),
+    Segment(Vec2, Vec2),
+}
+
+impl Shape {
+    pub fn distance(&self, point: Vec2) -> f32 {
+        match *self {
+            Shape::Point(position) => position.distance(point),
+            Shape::Segment(start, end) => {
+                let segment = end - start;
+                let t = if segment.length_squared() > 0. {
+                    ((point - start).dot(segment) / segment.length_squared()).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                start.lerp(end, t).distance(point)
+            }
+        }
+    }
+
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Shape::Point(position) => (position, position),
+            Shape::Segment(start, end) => (start.min(end), start.max(end)),
+        }
+    }
+}
+
+type Cell = (i32, i32);
+
+/// Uniform grid over points and segments. Each item is listed in every cell its shape's bounds
+/// touch, so a query only looks at the items in the cells around it.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    items: Vec<(T, Shape)>,
+    cells: HashMap<Cell, Vec<usize>>,
+    // cells with anything in them lie between these, so searches know when to stop
+    min_cell: Cell,
+    max_cell: Cell,
+}
+
+impl<T: Copy> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> SpatialGrid<T> {
+        SpatialGrid { cell_size, items: vec![], cells: HashMap::new(), min_cell: (0, 0), max_cell: (0, 0) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.cells.clear();
+    }
+
+    fn cell(&self, point: Vec2) -> Cell {
+        ((point.x / self.cell_size).floor() as i32, (point.y / self.cell_size).floor() as i32)
+    }
+
+    pub fn insert(&mut self, item: T, shape: Shape) {
+        let index = self.items.len();
+        let (low, high) = shape.bounds();
+        let (low, high) = (self.cell(low), self.cell(high));
+        if self.items.is_empty() {
+            (self.min_cell, self.max_cell) = (low, high);
+        } else {
+            self.min_cell = (self.min_cell.0.min(low.0), self.min_cell.1.min(low.1));
+            self.max_cell = (self.max_cell.0.max(high.0), self.max_cell.1.max(high.1));
+        }
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+        self.items.push((item, shape));
+    }
+
+    /// Everything within `radius` of `point`, nearest first
+    pub fn within(&self, point: Vec2, radius: f32) -> Vec<(T, f32)> {
+        let (low, high) = (self.cell(point - Vec2::splat(radius)), self.cell(point + Vec2::splat(radius)));
+        let mut seen = HashSet::new();
+        let mut found = vec![];
+        for x in low.0.max(self.min_cell.0)..=high.0.min(self.max_cell.0) {
+            for y in low.1.max(self.min_cell.1)..=high.1.min(self.max_cell.1) {
+                for index in self.cells.get(&(x, y)).into_iter().flatten() {
+                    if !seen.insert(*index) {
+                        continue;
+                    }
+                    let (item, shape) = self.items[*index];
+                    let distance = shape.distance(point);
+                    if distance <= radius {
+                        found.push((item, distance));
+                    }
+                }
+            }
+        }
+        found.sort_by_key(|(_, distance)| FloatOrd(*distance));
+        found
+    }
+
+    /// The `k` items nearest to `point` that `accept` lets through, nearest first. Searches
+    /// outwards a ring of cells at a time, and stops once nothing further out could be closer.
+    pub fn nearest(&self, point: Vec2, k: usize, mut accept: impl FnMut(T) -> bool) -> Vec<(T, f32)> {
+        if k == 0 || self.items.is_empty() {
+            return vec![];
+        }
+        let center = self.cell(point);
+        // rings past this one only hold empty cells
+        let last_ring = [
+            center.0 - self.min_cell.0,
+            self.max_cell.0 - center.0,
+            center.1 - self.min_cell.1,
+            self.max_cell.1 - center.1,
+        ].into_iter().max().unwrap_or(0).max(0);
+
+        let mut seen = HashSet::new();
+        let mut found: Vec<(T, f32)> = vec![];
+        for ring in 0..=last_ring {
+            for cell in ring_cells(center, ring) {
+                for index in self.cells.get(&cell).into_iter().flatten() {
+                    if !seen.insert(*index) {
+                        continue;
+                    }
+                    let (item, shape) = self.items[*index];
+                    if accept(item) {
+                        found.push((item, shape.distance(point)));
+                    }
+                }
+            }
+            found.sort_by_key(|(_, distance)| FloatOrd(*distance));
+            found.truncate(k);
+            // anything in the next ring is at least this far away
+            if found.len() == k && found[k - 1].1 <= ring as f32 * self.cell_size {
+                break;
+            }
+        }
+        found
+    }
+}
+
+/// The cells on the square `ring` cells out from `center`
+fn ring_cells(center: Cell, ring: i32) -> Vec<Cell> {
+    if ring == 0 {
+        return vec![center];
+    }
+    let mut cells = vec![];
+    for offset in -ring..=ring {
+        cells.push((center.0 + offset, center.1 - ring));
+        cells.push((center.0 + offset, center.1 + ring));
+    }
+    for offset in -ring + 1..ring {
+        cells.push((center.0 - ring, center.1 + offset));
+        cells.push((center.0 + ring, center.1 + offset));
+    }
+    cells
+}
+
+/// Where houses, road edges and paperboys are, for nearest and radius lookups
+#[derive(Resource)]
+pub struct SpatialIndex {
+    pub houses: SpatialGrid<Entity>,
+    pub roads: SpatialGrid<EdgeIndex>,
+    pub paperboys: SpatialGrid<Entity>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> SpatialIndex {
+        SpatialIndex {
+            houses: SpatialGrid::new(CELL_SIZE),
+            roads: SpatialGrid::new(CELL_SIZE),
+            paperboys: SpatialGrid::new(CELL_SIZE),
+        }
+    }
+}
+
+/// Indexes every edge of `world` by where it lies in the world
+pub fn index_roads(world: &GameWorld) -> SpatialGrid<EdgeIndex> {
+    let mut roads = SpatialGrid::new(CELL_SIZE);
+    for edge in world.graph.edge_indices() {
+        if let Some((start, end)) = world.graph.edge_endpoints(edge) {
+            let shape = Shape::Segment(graph::world_position(&world.graph[start]), graph::world_position(&world.graph[end]));
+            roads.insert(edge, shape);
+        }
+    }
+    roads
+}
+
+type MovedHouse = (With<models::House>, Or<(Added<models::House>, Changed<Transform>)>);
+
+/// Rebuilds the houses and roads when they change, and the paperboys every frame since they
+/// are nearly always moving
+pub fn update_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    world: Res<GameWorld>,
+    houses: Query<(Entity, &Transform), With<models::House>>,
+    changed_houses: Query<(), MovedHouse>,
+    mut removed_houses: RemovedComponents<models::House>,
+    paperboys: Query<(Entity, &Transform), With<crate::Paperboy>>,
+) {
+    if world.is_changed() {
+        index.roads = index_roads(&world);
+    }
+    if !changed_houses.is_empty() || removed_houses.iter().count() > 0 {
+        index.houses.clear();
+        for (house, transform) in &houses {
+            index.houses.insert(house, Shape::Point(transform.translation.truncate()));
+        }
+    }
+    index.paperboys.clear();
+    for (paperboy, transform) in &paperboys {
+        index.paperboys.insert(paperboy, Shape::Point(transform.translation.truncate()));
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::shift::StartShift;
+
+pub const MAX_STAMINA: f32 = 100.0;
+// below this a paperboy starts slowing down
+pub const TIRED_STAMINA: f32 = 25.0;
+// fraction of full speed left with no stamina at all
+const EXHAUSTED_SPEED: f32 = 0.5;
+// stamina regained per second near a rest spot
+const REST_RATE: f32 = 10.0;
+// how close to a rest spot a paperboy has to be to get their breath back
+const REST_RADIUS: f32 = 30.0;
+
+const REST_POINT_SIZE: Vec2 = Vec2::new(20., 20.);
+const REST_POINT_COLOR: Color = Color::rgb(0.9, 0.8, 0.3);
+
+/// How much more a paperboy can do before slowing down, out of `MAX_STAMINA`
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Stamina(pub f32);
+
+/// Somewhere paperboys get their stamina back: the depot and the map's rest points
+#[derive(Component)]
+pub struct RestSpot;
+
+/// Fraction of full speed a paperboy with `stamina` left can manage
+pub fn speed_factor(stamina: f32) -> f32 {
+    if stamina >= TIRED_STAMINA {
+        1.
+    } else {
+        EXHAUSTED_SPEED + (1. - EXHAUSTED_SPEED) * (stamina.max(0.) / TIRED_STAMINA)
+    }
+}
+
+pub fn spawn_rest_point(commands: &mut Commands, position: Vec2) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: REST_POINT_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                // under the paperboys resting on it
+                translation: position.extend(-0.5),
+                scale: REST_POINT_SIZE.extend(1.0),
+                ..default()
+            },
+            ..default()
+        },
+        RestSpot,
+    )).id()
+}
+
+/// New paperboys start rested
+pub fn equip_stamina(
+    mut commands: Commands,
+    paperboys: Query<Entity, (With<crate::Paperboy>, Without<Stamina>)>,
+) {
+    for paperboy in &paperboys {
+        commands.entity(paperboy).insert(Stamina(MAX_STAMINA));
+    }
+}
+
+pub fn rest_paperboys(
+    time: Res<Time>,
+    spots: Query<&Transform, With<RestSpot>>,
+    mut paperboys: Query<(&Transform, &mut Stamina), With<crate::Paperboy>>,
+) {
+    for (transform, mut stamina) in &mut paperboys {
+        let position = transform.translation.truncate();
+        if spots.iter().any(|spot| spot.translation.truncate().distance(position) < REST_RADIUS) {
+            stamina.0 = (stamina.0 + REST_RATE * time.delta_seconds()).min(MAX_STAMINA);
+        }
+    }
+}
+
+/// Everyone comes back rested for the next shift
+pub fn rest_between_shifts(mut start_shift: EventReader<StartShift>, mut paperboys: Query<&mut Stamina>) {
+    if start_shift.iter().count() == 0 {
+        return;
+    }
+    for mut stamina in &mut paperboys {
+        stamina.0 = MAX_STAMINA;
+    }
+}
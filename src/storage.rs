@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("ron.bak")
+}
+
+fn parse<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Loads the player's `what` kept at `path`, or the default if there is none yet. A file that
+/// can't be parsed is moved aside rather than overwritten, and the backup from the last
+/// successful save is used instead.
+pub fn load<T: DeserializeOwned + Default>(path: &Path, what: &str) -> T {
+    if !path.exists() {
+        return T::default();
+    }
+    match parse(path) {
+        Ok(value) => value,
+        Err(e) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let corrupt = path.with_extension(format!("ron.corrupt-{}", now));
+            warn!("{} at {:?} is corrupted ({}), moving it to {:?}", what, path, e, corrupt);
+            let _ = fs::rename(path, &corrupt);
+            match parse(&backup_path(path)) {
+                Ok(value) => {
+                    info!("restored the {} from its backup", what);
+                    value
+                }
+                Err(_) => T::default(),
+            }
+        }
+    }
+}
+
+/// Writes `value` to `path` through a temporary file, so a crash mid-write can't leave it
+/// half-written, and keeps what was there before as a backup
+pub fn save<T: Serialize>(value: &T, path: &Path) -> Result<(), String> {
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let temporary = path.with_extension("ron.tmp");
+    fs::write(&temporary, contents).map_err(|e| e.to_string())?;
+    if path.exists() {
+        fs::copy(path, backup_path(path)).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temporary, path).map_err(|e| e.to_string())
+}
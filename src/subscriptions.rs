@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models;
+use crate::scoring::DropZone;
+use crate::shift::{Shift, ShiftEnded, StartShift};
+
+// satisfaction changes, on a scale where 1 is delighted and 0 cancels
+const ON_TIME_GAIN: f32 = 0.05;
+const WRONG_SPOT_LOSS: f32 = 0.1;
+const LATE_LOSS: f32 = 0.15;
+// extra loss per second late
+const LATE_LOSS_PER_SECOND: f32 = 0.01;
+const MISSED_LOSS: f32 = 0.3;
+
+pub const MAILBOX_SIZE: Vec2 = Vec2::new(10., 10.);
+const MAILBOX_COLOR: Color = Color::rgb(0.3, 0.3, 0.8);
+const SATISFACTION_BAR_SIZE: Vec2 = Vec2::new(40., 5.);
+const HAPPY_COLOR: Color = Color::rgb(0.2, 0.8, 0.2);
+const UNHAPPY_COLOR: Color = Color::rgb(0.9, 0.2, 0.2);
+
+/// Where a subscriber wants their paper
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Preference {
+    Porch,
+    Mailbox,
+}
+
+/// A subscriber in a level, by index into the map's houses
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubscriptionSpec {
+    pub house: usize,
+    // (seconds into the shift the paper is wanted from, seconds into the shift it is due by)
+    pub window: (f32, f32),
+    pub preference: Preference,
+}
+
+/// A house that gets a paper every shift without ordering it
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub window: (f32, f32),
+    pub preference: Preference,
+    // 1 when delighted, the subscription is cancelled when this reaches 0
+    pub satisfaction: f32,
+    // whether this shift's order has been placed yet
+    pub ordered: bool,
+}
+
+impl Subscription {
+    pub fn new(spec: &SubscriptionSpec) -> Subscription {
+        Subscription { window: spec.window, preference: spec.preference, satisfaction: 1., ordered: false }
+    }
+
+    /// Adjusts satisfaction for a subscription paper landing in `zone`, `late_by` seconds past due
+    pub fn rate_delivery(&mut self, zone: DropZone, late_by: f32) {
+        let right_spot = matches!(
+            (self.preference, zone),
+            (Preference::Porch, DropZone::Porch(_)) | (Preference::Mailbox, DropZone::Mailbox(_))
+        );
+        let mut change = if right_spot { ON_TIME_GAIN } else { -WRONG_SPOT_LOSS };
+        if late_by > 0. {
+            change -= LATE_LOSS + late_by * LATE_LOSS_PER_SECOND;
+        }
+        self.satisfaction = (self.satisfaction + change).clamp(0., 1.);
+    }
+}
+
+/// Mailboxes stand on the lawn, to the right of the porch
+pub fn mailbox_position(house_position: Vec2) -> Vec2 {
+    house_position + Vec2::new(
+        (models::HOUSE_SIZE.x + MAILBOX_SIZE.x) / 2.,
+        -(models::HOUSE_SIZE.y + models::PORCH_DEPTH) / 2.,
+    )
+}
+
+/// No aim point is further than this from its house, for finding houses by their aim points
+pub const MAX_AIM_OFFSET: f32 = (models::HOUSE_SIZE.x + MAILBOX_SIZE.x) / 2. + (models::HOUSE_SIZE.y + models::PORCH_DEPTH) / 2.;
+
+/// The spot papers for this house should be thrown at
+pub fn aim_point(house_position: Vec2, subscription: Option<&Subscription>) -> Vec2 {
+    match subscription {
+        Some(Subscription { preference: Preference::Mailbox, .. }) => mailbox_position(house_position),
+        _ => models::porch_position(house_position),
+    }
+}
+
+/// Places each subscriber's order once the shift reaches the start of their window
+pub fn subscription_orders(
+    time: Res<Time>,
+    shift: Res<Shift>,
+    mut houses: Query<(&mut models::House, &mut Subscription, &mut Sprite)>,
+) {
+    let elapsed = shift.timer.elapsed_secs();
+    for (mut house, mut subscription, mut sprite) in &mut houses {
+        if subscription.ordered || elapsed < subscription.window.0 {
+            continue;
+        }
+        subscription.ordered = true;
+        let due_in = (subscription.window.1 - elapsed).max(0.);
+        models::add_order(&mut house, &mut sprite, models::Order {
+            deadline: time.elapsed_seconds() + due_in,
+            subscription: true,
+        });
+    }
+}
+
+/// Subscribers whose paper never came get a lot less happy
+pub fn miss_subscriptions(
+    mut shift_ended: EventReader<ShiftEnded>,
+    mut houses: Query<(&models::House, &mut Subscription)>,
+) {
+    for _ in shift_ended.iter() {
+        for (house, mut subscription) in &mut houses {
+            let missed = house.orders.iter().filter(|order| order.subscription).count();
+            subscription.satisfaction = (subscription.satisfaction - missed as f32 * MISSED_LOSS).max(0.);
+        }
+    }
+}
+
+pub fn reset_subscriptions(
+    mut start_shift: EventReader<StartShift>,
+    mut subscriptions: Query<&mut Subscription>,
+) {
+    if start_shift.iter().count() == 0 {
+        return;
+    }
+    for mut subscription in &mut subscriptions {
+        subscription.ordered = false;
+    }
+}
+
+pub fn cancel_subscriptions(mut commands: Commands, subscriptions: Query<(Entity, &Subscription)>) {
+    for (house, subscription) in &subscriptions {
+        if subscription.satisfaction <= 0. {
+            info!("house {:?} cancelled its subscription", house);
+            commands.entity(house).remove::<Subscription>();
+        }
+    }
+}
+
+/// Sprites drawn for a subscribed house: its mailbox if it wants one, and a satisfaction meter
+#[derive(Component)]
+pub enum SubscriptionMarker {
+    Mailbox(Entity),
+    Satisfaction(Entity),
+}
+
+impl SubscriptionMarker {
+    fn house(&self) -> Entity {
+        match self {
+            SubscriptionMarker::Mailbox(house) | SubscriptionMarker::Satisfaction(house) => *house,
+        }
+    }
+}
+
+/// Keeps the markers in step with the subscriptions, removing them when the house cancels or
+/// is despawned
+pub fn update_subscription_markers(
+    mut commands: Commands,
+    subscriptions: Query<(Entity, &Subscription, &Transform), Without<SubscriptionMarker>>,
+    mut markers: Query<(Entity, &SubscriptionMarker, &mut Sprite, &mut Transform)>,
+) {
+    for (marker_entity, marker, mut sprite, mut transform) in &mut markers {
+        let Ok((_, subscription, house_transform)) = subscriptions.get(marker.house()) else {
+            commands.entity(marker_entity).despawn();
+            continue;
+        };
+        if let SubscriptionMarker::Satisfaction(_) = marker {
+            // the bar shrinks towards its left end
+            let width = SATISFACTION_BAR_SIZE.x * subscription.satisfaction;
+            let left = house_transform.translation.x - SATISFACTION_BAR_SIZE.x / 2.;
+            transform.translation.x = left + width / 2.;
+            transform.scale.x = width;
+            sprite.color = if subscription.satisfaction > 0.5 { HAPPY_COLOR } else { UNHAPPY_COLOR };
+        }
+    }
+
+    for (house, subscription, house_transform) in &subscriptions {
+        let house_position = house_transform.translation.truncate();
+        if !markers.iter().any(|(_, marker, _, _)| marker.house() == house) {
+            let bar_position = house_position + Vec2::new(0., (models::HOUSE_SIZE.y + SATISFACTION_BAR_SIZE.y) / 2. + 2.);
+            spawn_marker(&mut commands, bar_position, SATISFACTION_BAR_SIZE, HAPPY_COLOR, SubscriptionMarker::Satisfaction(house));
+            if subscription.preference == Preference::Mailbox {
+                spawn_marker(&mut commands, mailbox_position(house_position), MAILBOX_SIZE, MAILBOX_COLOR, SubscriptionMarker::Mailbox(house));
+            }
+        }
+    }
+}
+
+fn spawn_marker(commands: &mut Commands, position: Vec2, size: Vec2, color: Color, marker: SubscriptionMarker) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                ..default()
+            },
+            transform: Transform {
+                translation: position.extend(0.5),
+                scale: size.extend(1.0),
+                ..default()
+            },
+            ..default()
+        },
+        marker,
+    ));
+}
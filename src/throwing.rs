@@ -0,0 +1,276 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::collide_aabb::{collide, Collision};
+use rand::Rng;
+
+use crate::economy::{self, Bag};
+use crate::input::{Action, ActionState};
+use crate::models;
+use crate::movement::Route;
+use crate::scoring::{self, DropZone, Score};
+use crate::spatial::SpatialIndex;
+use crate::subscriptions::{self, Preference, Subscription};
+use crate::vehicle::Vehicle;
+use crate::weather::Conditions;
+
+const PAPER_SIZE: Vec2 = Vec2::new(8., 5.);
+const PAPER_COLOR: Color = Color::rgb(0.6, 0.6, 0.55);
+// fraction of its velocity a paper loses per second, as a rate
+const PAPER_DRAG: f32 = 3.0;
+// below this speed a paper is considered to have landed
+const PAPER_REST_SPEED: f32 = 10.0;
+// fraction of speed kept when bouncing off a wall or house
+const PAPER_RESTITUTION: f32 = 0.5;
+// radians of spin per unit travelled
+const PAPER_SPIN: f32 = 0.15;
+// aim error at the fastest vehicle's top speed, in radians either side of the target
+const MAX_AIM_ERROR: f32 = 0.6;
+// error in throw strength at the fastest vehicle's top speed, as a fraction either side of the
+// right strength
+const MAX_STRENGTH_ERROR: f32 = 0.4;
+// seconds a landed paper stays on the ground before it is cleared away
+const LANDED_PAPER_LIFETIME: f32 = 5.0;
+
+/// Whether moving paperboys throw papers at their assigned houses by themselves
+#[derive(Resource)]
+pub struct AutoThrow(pub bool);
+
+impl Default for AutoThrow {
+    fn default() -> AutoThrow { AutoThrow(true) }
+}
+
+/// A paper sliding across the map after being thrown
+#[derive(Component, Debug)]
+pub struct Paper {
+    pub velocity: Vec2,
+}
+
+/// A paper that has come to rest and will be cleared away after a while
+#[derive(Component)]
+pub struct LandedPaper {
+    timer: Timer,
+}
+
+/// Launch velocity that makes a paper slow to a rest exactly at `target` under drag, before
+/// aim and strength errors scaled by `speed` are applied
+pub fn launch_velocity(from: Vec2, target: Vec2, speed: f32, rng: &mut impl Rng) -> Vec2 {
+    let offset = target - from;
+    // with linear drag a paper travels (v0 - rest speed) / drag before it counts as landed
+    let strength = offset.length() * PAPER_DRAG + PAPER_REST_SPEED;
+    let clumsiness = (speed / Vehicle::fastest_speed()).clamp(0., 1.);
+    let aim_error = rng.gen_range(-1.0..=1.0) * MAX_AIM_ERROR * clumsiness;
+    let strength_error = 1. + rng.gen_range(-1.0..=1.0) * MAX_STRENGTH_ERROR * clumsiness;
+    let direction = Vec2::from_angle(aim_error).rotate(offset.normalize_or_zero());
+    direction * strength * strength_error
+}
+
+pub fn throw_paper(commands: &mut Commands, from: Vec2, target: Vec2, speed: f32, rng: &mut impl Rng) {
+    let velocity = launch_velocity(from, target, speed, rng);
+    spawn_paper(commands, from, velocity);
+}
+
+pub fn spawn_paper(commands: &mut Commands, from: Vec2, velocity: Vec2) -> Entity {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: PAPER_COLOR,
+                ..default()
+            },
+            transform: Transform {
+                translation: from.extend(1.0),
+                scale: PAPER_SIZE.extend(1.0),
+                ..default()
+            },
+            ..default()
+        },
+        Paper { velocity },
+    )).id()
+}
+
+pub fn toggle_auto_throw(actions: Res<ActionState>, mut auto_throw: ResMut<AutoThrow>) {
+    if actions.just_pressed(Action::ToggleAutoThrow) {
+        auto_throw.0 = !auto_throw.0;
+        debug!("auto throw pressed, auto throw is now {:?}", auto_throw.0);
+    }
+}
+
+pub fn auto_throw(
+    mut commands: Commands,
+    auto_throw: Res<AutoThrow>,
+    current: Res<crate::levels::CurrentLevel>,
+    index: Res<SpatialIndex>,
+    mut game_rng: ResMut<crate::GameRng>,
+    mut paperboys: Query<(Entity, &Transform, &mut Route, &mut Bag), With<crate::Paperboy>>,
+    houses: Query<(&models::House, &Transform, Option<&Subscription>)>,
+) {
+    if !auto_throw.0 || !current.level.unlocks(crate::levels::Mechanic::AutoThrow) {
+        return;
+    }
+    let rng = &mut game_rng.rng;
+    for (paperboy, transform, mut route, mut bag) in &mut paperboys {
+        let position = transform.translation.truncate();
+        // houses are indexed by their middle, which can be a little way from where papers go
+        for (house_entity, _) in index.houses.within(position, crate::PAPERBOY_REACH + subscriptions::MAX_AIM_OFFSET) {
+            let Ok((house, house_transform, subscription)) = houses.get(house_entity) else { continue };
+            let target = subscriptions::aim_point(house_transform.translation.truncate(), subscription);
+            // one paper per order, a house waiting on two gets two
+            let thrown = route.attempted.iter().filter(|attempted| **attempted == house_entity).count();
+            if thrown >= house.orders.len()
+                || house.assigned_to != Some(paperboy)
+                || position.distance(target) >= crate::PAPERBOY_REACH {
+                continue;
+            }
+            if bag.papers == 0 {
+                break;
+            }
+
+            debug!("paperboy {:?} throws at house {:?} at speed {:?}", paperboy, house_entity, route.speed);
+            route.attempted.push(house_entity);
+            bag.papers -= 1;
+            throw_paper(&mut commands, position, target, route.speed, rng);
+            if bag.papers == 0 {
+                info!("paperboy {:?} is out of papers", paperboy);
+            }
+        }
+    }
+}
+
+/// Colliders papers bounce off: walls and houses, not roads, paths or paperboys
+type Bouncy = (With<crate::Collider>, Without<Paper>, Without<crate::Road>, Without<crate::PathSegment>, Without<crate::Paperboy>);
+
+pub fn move_papers(
+    time: Res<Time>,
+    mut papers: Query<(&mut Paper, &mut Transform)>,
+    colliders: Query<&Transform, Bouncy>,
+) {
+    let dt = time.delta_seconds();
+    for (mut paper, mut transform) in &mut papers {
+        let step = paper.velocity * dt;
+        transform.translation += step.extend(0.);
+        transform.rotate_z(step.length() * PAPER_SPIN);
+        paper.velocity *= (-PAPER_DRAG * dt).exp();
+
+        // bounce off walls and houses like the ball in breakout
+        for collider in &colliders {
+            let Some(collision) = collide(
+                transform.translation,
+                PAPER_SIZE,
+                collider.translation,
+                collider.scale.truncate().abs(),
+            ) else { continue };
+
+            // only reflect when moving into the collider, so a paper doesn't get stuck inside
+            let (reflect_x, reflect_y) = match collision {
+                Collision::Left => (paper.velocity.x > 0., false),
+                Collision::Right => (paper.velocity.x < 0., false),
+                Collision::Top => (false, paper.velocity.y < 0.),
+                Collision::Bottom => (false, paper.velocity.y > 0.),
+                Collision::Inside => (false, false),
+            };
+            if reflect_x {
+                paper.velocity.x = -paper.velocity.x * PAPER_RESTITUTION;
+            }
+            if reflect_y {
+                paper.velocity.y = -paper.velocity.y * PAPER_RESTITUTION;
+            }
+        }
+    }
+}
+
+type LandingHouse<'a> = (
+    Entity,
+    &'a mut models::House,
+    &'a Transform,
+    &'a mut Sprite,
+    Option<&'a mut Subscription>,
+);
+
+/// What a paper can land on, and whether it's wet
+#[derive(SystemParam)]
+pub struct Ground<'w, 's> {
+    conditions: Res<'w, Conditions>,
+    houses: Query<'w, 's, LandingHouse<'static>>,
+    roads: Query<'w, 's, &'static Transform, With<crate::Road>>,
+}
+
+pub fn land_papers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut score: ResMut<Score>,
+    papers: Query<(Entity, &Paper, &Transform)>,
+    ground: Ground,
+    mut routes: Query<&mut Route>,
+) {
+    let Ground { conditions, mut houses, roads } = ground;
+    for (entity, paper, transform) in &papers {
+        if paper.velocity.length() >= PAPER_REST_SPEED {
+            continue;
+        }
+
+        let landing = transform.translation.truncate();
+        let house_positions: Vec<(Entity, Vec2)> = houses.iter()
+            .map(|(entity, _, transform, _, _)| (entity, transform.translation.truncate()))
+            .collect();
+        let mailboxes: Vec<(Entity, Vec2)> = houses.iter()
+            .filter(|(_, _, _, _, subscription)| subscription.as_ref().is_some_and(|s| s.preference == Preference::Mailbox))
+            .map(|(entity, _, transform, _, _)| (entity, subscriptions::mailbox_position(transform.translation.truncate())))
+            .collect();
+        let road_rects: Vec<(Vec2, Vec2)> = roads.iter()
+            .map(|transform| (transform.translation.truncate(), transform.scale.truncate()))
+            .collect();
+        let zone = scoring::classify_landing(landing, &house_positions, &mailboxes, &road_rects);
+
+        let points = match zone {
+            DropZone::Lawn(_) if conditions.rain => {
+                debug!("paper landed on a wet lawn and is ruined");
+                0
+            }
+            DropZone::Porch(house_entity) | DropZone::Mailbox(house_entity) | DropZone::Lawn(house_entity) => {
+                let (_, mut house, _, mut sprite, subscription) = houses.get_mut(house_entity).unwrap();
+                let assigned_to = house.assigned_to;
+                if let Some(order) = models::mark_delivered(&mut house, &mut sprite) {
+                    // the order is served, so its throw no longer holds back one for a later order
+                    if let Some(mut route) = assigned_to.and_then(|paperboy| routes.get_mut(paperboy).ok()) {
+                        if let Some(index) = route.attempted.iter().position(|house| *house == house_entity) {
+                            route.attempted.remove(index);
+                        }
+                    }
+                    let late_by = time.elapsed_seconds() - order.deadline;
+                    if late_by > 0. {
+                        score.late_deliveries += 1;
+                        score.lateness += late_by;
+                    }
+                    score.wages += economy::WAGE;
+                    score.tips += economy::tip(&order, time.elapsed_seconds());
+                    if let (true, Some(mut subscription)) = (order.subscription, subscription) {
+                        subscription.rate_delivery(zone, late_by);
+                    }
+                    score.deliveries += 1;
+                    zone.points()
+                } else {
+                    // nobody ordered this one
+                    0
+                }
+            }
+            DropZone::Road | DropZone::Nowhere => zone.points(),
+        };
+        score.points += points;
+        debug!("paper landed at {:?} in zone {:?}, scoring {}", landing, zone, points);
+
+        commands.entity(entity).remove::<Paper>().insert(LandedPaper {
+            timer: Timer::from_seconds(LANDED_PAPER_LIFETIME, TimerMode::Once),
+        });
+    }
+}
+
+pub fn clear_landed_papers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut papers: Query<(Entity, &mut LandedPaper)>,
+) {
+    for (entity, mut paper) in &mut papers {
+        if paper.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
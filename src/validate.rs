@@ -0,0 +1,193 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::utils::FloatOrd;
+use bevy::utils::petgraph::graph::NodeIndex;
+
+use crate::graph::{self, EdgeKind, GameWorld};
+use crate::map::MapData;
+use crate::models;
+
+// graph units closer than this count as the same place
+const EPSILON: f32 = 1e-4;
+
+/// Something wrong with a map's road graph. Nodes are indices into the map's `graph.nodes`,
+/// edges are (start, end) pairs of them and houses index the map's `houses`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    NoRoads,
+    // two nodes in the same place, where one was surely meant
+    DuplicateNodes { first: usize, second: usize },
+    ZeroLengthEdge { edge: (usize, usize) },
+    // an edge without one going back the other way
+    OneWay { edge: (usize, usize), kind: EdgeKind },
+    // two edges running along each other
+    Overlap { first: (usize, usize), second: (usize, usize) },
+    // two edges that cross or touch without a node joining them
+    Crossing { first: (usize, usize), second: (usize, usize), at: Vec2 },
+    UnreachableNode { node: usize },
+    UnreachableHouse { house: usize },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::NoRoads => write!(f, "the map has no roads"),
+            Problem::DuplicateNodes { first, second } => write!(f, "nodes {} and {} are in the same place", first, second),
+            Problem::ZeroLengthEdge { edge } => write!(f, "edge {:?} has no length", edge),
+            Problem::OneWay { edge, kind } => write!(f, "{:?} {:?} has no edge back the other way", kind, edge),
+            Problem::Overlap { first, second } => write!(f, "edges {:?} and {:?} overlap", first, second),
+            Problem::Crossing { first, second, at } => {
+                write!(f, "edges {:?} and {:?} cross at ({}, {}) without a node there", first, second, at.x, at.y)
+            }
+            Problem::UnreachableNode { node } => write!(f, "node {} can't be reached from the depot", node),
+            Problem::UnreachableHouse { house } => write!(f, "house {} can't be reached from the depot", house),
+        }
+    }
+}
+
+/// Checks the road graph of `map`, with its depot at the spawn point
+pub fn validate_map(map: &MapData) -> Vec<Problem> {
+    validate(&GameWorld::from(&map.graph), &map.houses, map.spawn)
+}
+
+/// Everything wrong with `world`, given the world positions of its houses and the depot
+pub fn validate(world: &GameWorld, houses: &[Vec2], depot: Vec2) -> Vec<Problem> {
+    let graph = &world.graph;
+    if graph.edge_count() == 0 {
+        return vec![Problem::NoRoads];
+    }
+    let mut problems = vec![];
+    let position = |node: NodeIndex| graph[node].pos;
+
+    for first in graph.node_indices() {
+        for second in graph.node_indices().filter(|second| second.index() > first.index()) {
+            if position(first).distance(position(second)) < EPSILON {
+                problems.push(Problem::DuplicateNodes { first: first.index(), second: second.index() });
+            }
+        }
+    }
+
+    let edges: Vec<(NodeIndex, NodeIndex, EdgeKind)> = graph.edge_indices()
+        .filter_map(|edge| graph.edge_endpoints(edge).map(|(start, end)| (start, end, graph[edge].kind)))
+        .collect();
+    let pair = |start: NodeIndex, end: NodeIndex| (start.index(), end.index());
+
+    for (start, end, kind) in &edges {
+        if position(*start).distance(position(*end)) < EPSILON {
+            problems.push(Problem::ZeroLengthEdge { edge: pair(*start, *end) });
+        }
+        if !edges.contains(&(*end, *start, *kind)) {
+            problems.push(Problem::OneWay { edge: pair(*start, *end), kind: *kind });
+        }
+    }
+
+    // each road once, whichever way it was drawn; a pair drawn twice the same way overlaps itself
+    let mut seen = HashSet::new();
+    let mut segments = vec![];
+    for (start, end, _) in &edges {
+        let key = (start.index().min(end.index()), start.index().max(end.index()));
+        if !seen.insert((key, start.index() < end.index())) {
+            problems.push(Problem::Overlap { first: pair(*start, *end), second: pair(*start, *end) });
+        } else if !segments.contains(&key) {
+            segments.push(key);
+        }
+    }
+    for (i, first) in segments.iter().enumerate() {
+        for second in &segments[i + 1..] {
+            let a = (position(NodeIndex::new(first.0)), position(NodeIndex::new(first.1)));
+            let b = (position(NodeIndex::new(second.0)), position(NodeIndex::new(second.1)));
+            match meeting(a, b) {
+                Meeting::Overlap => problems.push(Problem::Overlap { first: *first, second: *second }),
+                Meeting::Crossing(at) => problems.push(Problem::Crossing { first: *first, second: *second, at }),
+                Meeting::Apart => {}
+            }
+        }
+    }
+
+    // everything is reached from the node nearest the depot, going the way the edges point
+    let world_position = |node: NodeIndex| graph::world_position(&graph[node]);
+    let Some(start) = graph.node_indices().min_by_key(|node| FloatOrd(world_position(*node).distance(depot))) else {
+        return problems;
+    };
+    let mut reached = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in graph.neighbors(node) {
+            if reached.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    for node in graph.node_indices().filter(|node| !reached.contains(node)) {
+        problems.push(Problem::UnreachableNode { node: node.index() });
+    }
+    for (index, house) in houses.iter().enumerate() {
+        let porch = models::porch_position(*house);
+        let nearest = edges.iter().min_by_key(|(start, end, _)| {
+            FloatOrd(distance_to_segment(porch, world_position(*start), world_position(*end)))
+        });
+        if let Some((start, end, _)) = nearest {
+            if !reached.contains(start) && !reached.contains(end) {
+                problems.push(Problem::UnreachableHouse { house: index });
+            }
+        }
+    }
+    problems
+}
+
+/// Whether two segments cross, touch somewhere other than a shared end, or run along each other
+pub fn segments_cross(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    !matches!(meeting(a, b), Meeting::Apart)
+}
+
+enum Meeting {
+    Apart,
+    Overlap,
+    Crossing(Vec2),
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// How two segments meet. Segments sharing an end only meet there, which is fine, unless they
+/// run on along each other.
+fn meeting(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> Meeting {
+    let (p, r) = (a.0, a.1 - a.0);
+    let (q, s) = (b.0, b.1 - b.0);
+    let denominator = cross(r, s);
+    let shares_end = [a.0, a.1].iter().any(|end| end.distance(b.0) < EPSILON || end.distance(b.1) < EPSILON);
+
+    if denominator.abs() < EPSILON {
+        // parallel: only a problem on the same line with some length in common
+        if cross(q - p, r).abs() > EPSILON || r.length_squared() < EPSILON {
+            return Meeting::Apart;
+        }
+        let along = |point: Vec2| (point - p).dot(r) / r.length_squared();
+        let (t0, t1) = (along(q).min(along(q + s)), along(q).max(along(q + s)));
+        let common = t1.min(1.) - t0.max(0.);
+        return if common * r.length() > EPSILON { Meeting::Overlap } else { Meeting::Apart };
+    }
+    if shares_end {
+        return Meeting::Apart;
+    }
+    let t = cross(q - p, s) / denominator;
+    let u = cross(q - p, r) / denominator;
+    if (-EPSILON..=1. + EPSILON).contains(&t) && (-EPSILON..=1. + EPSILON).contains(&u) {
+        Meeting::Crossing(p + r * t)
+    } else {
+        Meeting::Apart
+    }
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let t = if segment.length_squared() > 0. {
+        ((point - start).dot(segment) / segment.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    start.lerp(end, t).distance(point)
+}
@@ -0,0 +1,151 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::stamina;
+
+// world units estimated at a time when working out how tiredness slows a route down
+const STAMINA_STEP: f32 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VehicleKind {
+    Walker,
+    Bicycle,
+    Scooter,
+}
+
+/// How a paperboy gets around
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Vehicle {
+    pub kind: VehicleKind,
+    // world units per second
+    pub max_speed: f32,
+    // world units per second, per second
+    pub acceleration: f32,
+    // fraction of speed lost taking a right angle turn, sharper turns lose no more
+    pub turn_penalty: f32,
+    // whether footpaths can be used
+    pub footpaths: bool,
+    // stamina used per 100 world units covered
+    pub stamina_drain: f32,
+    pub size: Vec2,
+    pub color: Color,
+}
+
+impl Vehicle {
+    pub fn new(kind: VehicleKind) -> Vehicle {
+        match kind {
+            VehicleKind::Walker => Vehicle {
+                kind,
+                max_speed: crate::PAPERBOY_SPEED,
+                acceleration: 120.,
+                turn_penalty: 0.1,
+                footpaths: true,
+                stamina_drain: 10.,
+                size: crate::PAPERBOY_SIZE,
+                color: crate::PAPERBOY_COLOR,
+            },
+            VehicleKind::Bicycle => Vehicle {
+                kind,
+                max_speed: 90.,
+                acceleration: 60.,
+                turn_penalty: 0.4,
+                footpaths: false,
+                stamina_drain: 6.,
+                size: Vec2::new(14., 8.),
+                color: Color::rgb(0.1, 0.5, 0.9),
+            },
+            VehicleKind::Scooter => Vehicle {
+                kind,
+                max_speed: 120.,
+                acceleration: 40.,
+                turn_penalty: 0.6,
+                footpaths: false,
+                stamina_drain: 2.,
+                size: Vec2::new(16., 10.),
+                color: Color::rgb(0.5, 0.2, 0.8),
+            },
+        }
+    }
+
+    /// Top speed of the fastest vehicle there is
+    pub fn fastest_speed() -> f32 {
+        [VehicleKind::Walker, VehicleKind::Bicycle, VehicleKind::Scooter].into_iter()
+            .map(|kind| Vehicle::new(kind).max_speed)
+            .fold(0., f32::max)
+    }
+
+    /// Fraction of its speed kept going from direction `from` into direction `to`
+    pub fn speed_kept_turning(&self, from: Vec2, to: Vec2) -> f32 {
+        if from == Vec2::ZERO || to == Vec2::ZERO {
+            return 1.;
+        }
+        let sharpness = (from.angle_between(to).abs() / FRAC_PI_2).min(1.);
+        1. - self.turn_penalty * sharpness
+    }
+
+    /// Stamina used covering `length`
+    pub fn drain(&self, length: f32) -> f32 {
+        length * self.stamina_drain / 100.
+    }
+
+    /// Seconds taken to cover `length` starting at `speed` without going over `max_speed`, and
+    /// the speed at the end
+    pub fn cover(&self, length: f32, speed: f32, max_speed: f32) -> (f32, f32) {
+        let speed = speed.min(max_speed);
+        let to_max_speed = (max_speed - speed) / self.acceleration;
+        let accelerating = (speed + max_speed) / 2. * to_max_speed;
+        if accelerating >= length {
+            // still speeding up at the end: solve length = speed t + acceleration t^2 / 2
+            let t = ((speed * speed + 2. * self.acceleration * length).sqrt() - speed) / self.acceleration;
+            (t, speed + self.acceleration * t)
+        } else {
+            (to_max_speed + (length - accelerating) / max_speed, max_speed)
+        }
+    }
+
+    /// Seconds to follow `points` from a standstill starting with `stamina`, slowing down at
+    /// every turn and once tired, and the stamina left at the end
+    pub fn travel_time(&self, points: &[Vec2], mut stamina: f32) -> (f32, f32) {
+        let mut time = 0.;
+        let mut speed = 0.;
+        for (index, pair) in points.windows(2).enumerate() {
+            if index > 0 {
+                speed *= self.speed_kept_turning(pair[0] - points[index - 1], pair[1] - pair[0]);
+            }
+            // tiredness creeps in along a segment, so take it a step at a time
+            let length = pair[0].distance(pair[1]);
+            let steps = (length / STAMINA_STEP).ceil().max(1.);
+            for _ in 0..steps as u32 {
+                let max_speed = self.max_speed * stamina::speed_factor(stamina);
+                let (taken, end_speed) = self.cover(length / steps, speed, max_speed);
+                time += taken;
+                speed = end_speed;
+                stamina = (stamina - self.drain(length / steps)).max(0.);
+            }
+        }
+        (time, stamina)
+    }
+
+    /// Rough seconds lost to a turn: the speed shed has to be made up again
+    pub fn turn_time(&self, from: Vec2, to: Vec2) -> f32 {
+        let lost = self.max_speed * (1. - self.speed_kept_turning(from, to));
+        lost / self.acceleration / 2.
+    }
+}
+
+type Unequipped = (With<crate::Paperboy>, Without<Vehicle>);
+
+/// Gives paperboys without a vehicle the best one the profile owns, sized and coloured to match
+pub fn equip_vehicles(
+    mut commands: Commands,
+    profile: Res<crate::economy::Profile>,
+    mut paperboys: Query<(Entity, &mut Transform), Unequipped>,
+) {
+    for (paperboy, mut transform) in &mut paperboys {
+        let vehicle = Vehicle::new(profile.vehicle());
+        transform.scale = vehicle.size.extend(1.0);
+        commands.entity(paperboy).insert(vehicle);
+    }
+}
@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::levels::CurrentLevel;
+use crate::models;
+use crate::shift::Shift;
+
+// fraction of their speed paperboys keep in the rain
+const RAIN_SPEED: f32 = 0.8;
+// how far a paperboy can see at midnight and in full daylight
+const NIGHT_VISIBILITY: f32 = 120.0;
+const DAY_VISIBILITY: f32 = 600.0;
+// how far away houses can still be made out in fog
+const FOG_VISIBILITY: f32 = 180.0;
+
+const NIGHT_CLEAR_COLOR: Color = Color::rgb(0.1, 0.1, 0.2);
+// drawn over everything, at full strength at midnight
+const NIGHT_TINT: Color = Color::rgba(0.05, 0.05, 0.25, 0.45);
+const RAIN_TINT: Color = Color::rgba(0.3, 0.35, 0.5, 0.2);
+const FOG_TINT: Color = Color::rgba(0.8, 0.8, 0.8, 0.3);
+const NIGHT_SHROUD_COLOR: Color = Color::rgb(0.1, 0.1, 0.2);
+const FOG_SHROUD_COLOR: Color = Color::rgb(0.75, 0.75, 0.75);
+// big enough to cover the map at any zoom
+const TINT_SIZE: Vec2 = Vec2::new(20000., 20000.);
+// shrouds cover the house and its porch
+const SHROUD_SIZE: Vec2 = Vec2::new(models::HOUSE_SIZE.x + 4., models::HOUSE_SIZE.y + models::PORCH_DEPTH + 4.);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Weather {
+    // slows paperboys down and ruins papers that land on lawns
+    Rain,
+    // hides houses, and so their orders, away from the paperboys
+    Fog,
+}
+
+/// Weather over part of a shift
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Spell {
+    pub weather: Weather,
+    // seconds into the shift
+    pub from: f32,
+    pub to: f32,
+}
+
+/// Light and weather right now, worked out from the level every frame
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Conditions {
+    // 0 at midnight, 1 in full daylight
+    pub daylight: f32,
+    pub rain: bool,
+    pub fog: bool,
+}
+
+impl Default for Conditions {
+    fn default() -> Conditions {
+        Conditions { daylight: 1., rain: false, fog: false }
+    }
+}
+
+impl Conditions {
+    /// Fraction of full speed the weather lets paperboys move at
+    pub fn speed_factor(&self) -> f32 {
+        if self.rain { RAIN_SPEED } else { 1. }
+    }
+
+    /// How far a paperboy can see, or None in full daylight
+    pub fn visibility(&self) -> Option<f32> {
+        (self.daylight < 1.).then_some(NIGHT_VISIBILITY + (DAY_VISIBILITY - NIGHT_VISIBILITY) * self.daylight)
+    }
+}
+
+/// Daylight `elapsed` seconds into a shift, from the level's `(seconds, daylight)` points
+fn daylight_at(points: &[(f32, f32)], elapsed: f32) -> f32 {
+    let Some(first) = points.first() else { return 1. };
+    if elapsed <= first.0 {
+        return first.1.clamp(0., 1.);
+    }
+    for pair in points.windows(2) {
+        let ((t0, d0), (t1, d1)) = (pair[0], pair[1]);
+        if elapsed <= t1 {
+            let t = if t1 > t0 { (elapsed - t0) / (t1 - t0) } else { 1. };
+            return (d0 + (d1 - d0) * t).clamp(0., 1.);
+        }
+    }
+    points[points.len() - 1].1.clamp(0., 1.)
+}
+
+pub fn update_conditions(
+    shift: Res<Shift>,
+    current: Res<CurrentLevel>,
+    mut conditions: ResMut<Conditions>,
+) {
+    let elapsed = shift.timer.elapsed_secs();
+    let under = |weather: Weather| current.level.weather.iter()
+        .any(|spell| spell.weather == weather && spell.from <= elapsed && elapsed < spell.to);
+    let now = Conditions {
+        daylight: daylight_at(&current.level.daylight, elapsed),
+        rain: under(Weather::Rain),
+        fog: under(Weather::Fog),
+    };
+    if now.rain != conditions.rain || now.fog != conditions.fog {
+        info!("weather changed: rain {}, fog {}", now.rain, now.fog);
+    }
+    if *conditions != now {
+        *conditions = now;
+    }
+}
+
+/// The lighting drawn over the whole map
+#[derive(Component)]
+pub enum Tint {
+    Night,
+    Rain,
+    Fog,
+}
+
+pub fn setup_tints(mut commands: Commands) {
+    for (tint, z) in [(Tint::Night, 5.), (Tint::Rain, 5.1), (Tint::Fog, 5.2)] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::NONE,
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(0., 0., z),
+                    scale: TINT_SIZE.extend(1.0),
+                    ..default()
+                },
+                ..default()
+            },
+            tint,
+        ));
+    }
+}
+
+pub fn update_tints(
+    conditions: Res<Conditions>,
+    mut clear_color: ResMut<ClearColor>,
+    mut tints: Query<(&Tint, &mut Sprite)>,
+) {
+    if !conditions.is_changed() {
+        return;
+    }
+    let darkness = 1. - conditions.daylight;
+    let [r, g, b, _] = crate::BACKGROUND_COLOR.as_rgba_f32();
+    let [nr, ng, nb, _] = NIGHT_CLEAR_COLOR.as_rgba_f32();
+    clear_color.0 = Color::rgb(r + (nr - r) * darkness, g + (ng - g) * darkness, b + (nb - b) * darkness);
+
+    for (tint, mut sprite) in &mut tints {
+        sprite.color = match tint {
+            Tint::Night => NIGHT_TINT.with_a(NIGHT_TINT.a() * darkness),
+            Tint::Rain if conditions.rain => RAIN_TINT,
+            Tint::Fog if conditions.fog => FOG_TINT,
+            _ => Color::NONE,
+        };
+    }
+}
+
+/// Covers a house nobody can see, so whether it has ordered can't be told: at night when it's
+/// beyond every paperboy's sight, and in fog when it's too far away to make out
+#[derive(Component)]
+pub struct Shroud {
+    pub house: Entity,
+}
+
+type Houses = (With<models::House>, Without<Shroud>);
+
+pub fn update_shrouds(
+    mut commands: Commands,
+    conditions: Res<Conditions>,
+    houses: Query<(Entity, &Transform), Houses>,
+    paperboys: Query<&Transform, (With<crate::Paperboy>, Without<Shroud>)>,
+    mut shrouds: Query<(Entity, &Shroud, &mut Sprite, &mut Visibility)>,
+) {
+    let paperboy_positions: Vec<Vec2> = paperboys.iter().map(|transform| transform.translation.truncate()).collect();
+    let nearest = |position: Vec2| paperboy_positions.iter()
+        .map(|paperboy| paperboy.distance(position))
+        .fold(f32::INFINITY, f32::min);
+
+    for (entity, shroud, mut sprite, mut visibility) in &mut shrouds {
+        let Ok((_, transform)) = houses.get(shroud.house) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let distance = nearest(transform.translation.truncate());
+        let dark = conditions.visibility().is_some_and(|visible| distance > visible);
+        let fogged = conditions.fog && distance > FOG_VISIBILITY;
+        *visibility = if dark || fogged { Visibility::Inherited } else { Visibility::Hidden };
+        sprite.color = if dark { NIGHT_SHROUD_COLOR } else { FOG_SHROUD_COLOR };
+    }
+
+    for (house, transform) in &houses {
+        if !shrouds.iter().any(|(_, shroud, _, _)| shroud.house == house) {
+            commands.spawn((
+                SpriteBundle {
+                    transform: Transform {
+                        // above the house and its order count
+                        translation: (transform.translation.truncate() - Vec2::new(0., models::PORCH_DEPTH / 2.)).extend(4.0),
+                        scale: SHROUD_SIZE.extend(1.0),
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                Shroud { house },
+            ));
+        }
+    }
+}
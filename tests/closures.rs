@@ -0,0 +1,103 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::utils::petgraph::graph::NodeIndex;
+
+use common::Harness;
+use parallel_paperboy::closures::{self, Barrier, Closure, ClosureKind};
+use parallel_paperboy::graph::{self, GameWorld};
+use parallel_paperboy::levels::Level;
+use parallel_paperboy::models::House;
+use parallel_paperboy::movement::Route;
+use parallel_paperboy::routing::{self, WaitingForRoad};
+use parallel_paperboy::vehicle::{Vehicle, VehicleKind};
+
+// the builtin map's bottom road between D and E, and the dead end from F to I, as in
+// `graph::create_graph`
+const D: usize = 3;
+const E: usize = 4;
+const F: usize = 5;
+const I: usize = 8;
+
+fn position(world: &GameWorld, node: usize) -> Vec2 {
+    graph::world_position(&world.graph[NodeIndex::new(node)])
+}
+
+fn closing(edge: (usize, usize), to: f32) -> Harness {
+    Harness::new(Level {
+        orders: vec![],
+        closures: vec![Closure { kind: ClosureKind::Construction, edge, from: 0., to }],
+        ..Level::sandbox()
+    })
+}
+
+#[test]
+fn closed_roads_are_routed_around() {
+    let mut world = graph::create_graph();
+    let walker = Vehicle::new(VehicleKind::Walker);
+    let (d, e) = (NodeIndex::new(D), NodeIndex::new(E));
+    let (_, direct) = routing::find_route(&world, d, e, &walker).unwrap();
+    assert_eq!(direct, vec![d, e]);
+
+    for edge in [world.graph.find_edge(d, e).unwrap(), world.graph.find_edge(e, d).unwrap()] {
+        world.graph[edge].closed = true;
+        assert_eq!(routing::edge_cost(&world, edge, &walker), None);
+    }
+    let (_, around) = routing::find_route(&world, d, e, &walker).unwrap();
+    assert!(around.len() > 2);
+    assert!(!around.windows(2).any(|pair| pair == [d, e] || pair == [e, d]));
+}
+
+#[test]
+fn a_paperboy_headed_through_a_closure_is_sent_around_it() {
+    let mut game = closing((D, E), 1000.);
+    game.frame();
+    assert_eq!(game.count::<Barrier>(), 1);
+
+    let world = game.app.world.resource::<GameWorld>().clone();
+    let (start, past, end) = (position(&world, D), position(&world, E), position(&world, F));
+    let paperboy = game.selected_paperboy();
+    let house = game.app.world.query_filtered::<Entity, With<House>>().iter(&game.app.world).next().unwrap();
+    game.get_mut::<Transform>(paperboy).translation = start.extend(0.);
+    game.app.world.entity_mut(paperboy).insert(Route { attempted: vec![house], ..Route::new(vec![past, end]) });
+    game.frame();
+
+    let barrier = game.app.world.query_filtered::<&Transform, With<Barrier>>().single(&game.app.world).translation.truncate();
+    let route = game.get::<Route>(paperboy);
+    // around to where it was headed, then on along the rest of its route
+    assert_eq!(route.waypoints.iter().rev().take(2).collect::<Vec<_>>(), vec![&end, &past]);
+    assert!(route.waypoints.len() > 2);
+    assert!(!closures::blocked(start, &route.waypoints, &[barrier]));
+    assert_eq!(route.attempted, vec![house]);
+}
+
+#[test]
+fn a_paperboy_with_no_way_around_waits_for_the_road_to_reopen() {
+    let mut game = closing((F, I), 2.);
+    game.frame();
+    let world = game.app.world.resource::<GameWorld>().clone();
+    let paperboy = game.selected_paperboy();
+    game.get_mut::<Transform>(paperboy).translation = position(&world, F).extend(0.);
+    game.app.world.entity_mut(paperboy).insert(Route::new(vec![position(&world, I)]));
+    game.frame();
+    assert!(game.app.world.get::<Route>(paperboy).is_none());
+    assert!(game.app.world.get::<WaitingForRoad>(paperboy).is_some());
+
+    game.advance(3.);
+    assert!(game.app.world.get::<WaitingForRoad>(paperboy).is_none());
+    // and on its way again
+    let now = game.get::<Transform>(paperboy).translation.truncate();
+    assert!(now.distance(position(&world, I)) < position(&world, F).distance(position(&world, I)));
+}
+
+#[test]
+fn closed_roads_reopen_when_the_closure_ends() {
+    let mut game = closing((D, E), 2.);
+    game.frame();
+    assert_eq!(game.count::<Barrier>(), 1);
+    assert!(game.app.world.resource::<GameWorld>().graph.edge_weights().any(|edge| edge.closed));
+
+    game.advance(3.);
+    assert_eq!(game.count::<Barrier>(), 0);
+    assert!(!game.app.world.resource::<GameWorld>().graph.edge_weights().any(|edge| edge.closed));
+}
@@ -0,0 +1,120 @@
+//! A headless `App` running the simulation, with synthetic input and a clock that only moves
+//! when a test says so
+
+// each test file uses its own share of the harness
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::MouseButtonInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use parallel_paperboy::camera::CursorWorldPosition;
+use parallel_paperboy::economy::Profile;
+use parallel_paperboy::input::KeyBindings;
+use parallel_paperboy::leaderboard::Leaderboard;
+use parallel_paperboy::levels::{Campaign, Level, Progress};
+use parallel_paperboy::{GameRng, Paperboy, Selected, SimulationPlugin};
+
+// every update advances the game clock by exactly this much
+pub const FRAME: Duration = Duration::from_millis(1000 / 60);
+
+pub struct Harness {
+    pub app: App,
+    now: Instant,
+}
+
+impl Harness {
+    /// A fresh game on `level`, with default bindings, an empty profile and a fixed seed, so
+    /// nothing is read from the player's files
+    pub fn new(level: Level) -> Harness {
+        let now = Instant::now();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::input::InputPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualInstant(now))
+            .insert_resource(Campaign { levels: vec![level] })
+            .insert_resource(Progress::default())
+            .insert_resource(Profile::default())
+            .insert_resource(Leaderboard::default())
+            .insert_resource(KeyBindings::default())
+            .insert_resource(GameRng::from_seed(0))
+            .add_plugin(SimulationPlugin);
+        let mut harness = Harness { app, now };
+        // startup systems, then loading the level they ask for
+        harness.frames(2);
+        harness
+    }
+
+    /// The sandbox level with no random orders, so only the test places them
+    pub fn quiet_sandbox() -> Harness {
+        Harness::new(Level { orders: vec![], ..Level::sandbox() })
+    }
+
+    pub fn frame(&mut self) {
+        self.now += FRAME;
+        self.app.insert_resource(TimeUpdateStrategy::ManualInstant(self.now));
+        self.app.update();
+    }
+
+    pub fn frames(&mut self, count: u32) {
+        for _ in 0..count {
+            self.frame();
+        }
+    }
+
+    /// Runs as many frames as fit in `seconds` of game time
+    pub fn advance(&mut self, seconds: f32) {
+        self.frames((seconds / FRAME.as_secs_f32()).ceil() as u32);
+    }
+
+    /// Presses `key` for one frame, then lets it go
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ButtonState::Pressed);
+        self.frame();
+        self.send_key(key, ButtonState::Released);
+        self.frame();
+    }
+
+    /// Clicks the left mouse button with the cursor at `position` in world coordinates
+    pub fn click(&mut self, position: Vec2) {
+        self.app.insert_resource(CursorWorldPosition(Some(position)));
+        self.send_mouse(MouseButton::Left, ButtonState::Pressed);
+        self.frame();
+        self.send_mouse(MouseButton::Left, ButtonState::Released);
+        self.frame();
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ButtonState) {
+        self.app.world.send_event(KeyboardInput { scan_code: 0, key_code: Some(key), state });
+    }
+
+    fn send_mouse(&mut self, button: MouseButton, state: ButtonState) {
+        self.app.world.send_event(MouseButtonInput { button, state });
+    }
+
+    pub fn count<C: Component>(&mut self) -> usize {
+        self.app.world.query_filtered::<(), With<C>>().iter(&self.app.world).count()
+    }
+
+    /// The only entity with `C`, panicking if there isn't exactly one
+    pub fn single<C: Component>(&mut self) -> Entity {
+        self.app.world.query_filtered::<Entity, With<C>>().single(&self.app.world)
+    }
+
+    /// The paperboy the player has selected
+    pub fn selected_paperboy(&mut self) -> Entity {
+        self.app.world.query_filtered::<Entity, (With<Paperboy>, With<Selected>)>().single(&self.app.world)
+    }
+
+    pub fn get<C: Component>(&self, entity: Entity) -> &C {
+        self.app.world.get::<C>(entity).expect("entity is missing the component")
+    }
+
+    pub fn get_mut<C: Component>(&mut self, entity: Entity) -> Mut<'_, C> {
+        self.app.world.get_mut::<C>(entity).expect("entity is missing the component")
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::utils::petgraph::graph::NodeIndex;
+
+use common::Harness;
+use parallel_paperboy::dogs::{Dog, DogSpec, DogState};
+use parallel_paperboy::economy::Bag;
+use parallel_paperboy::graph::{self, GameWorld};
+use parallel_paperboy::levels::Level;
+use parallel_paperboy::models;
+use parallel_paperboy::routing;
+use parallel_paperboy::vehicle::{Vehicle, VehicleKind};
+
+const TERRITORY: f32 = 100.0;
+
+/// The sandbox with a dog at its first house, and where that dog lives
+fn game_with_dog() -> (Harness, Vec2) {
+    let game = Harness::new(Level {
+        orders: vec![],
+        dogs: vec![DogSpec { house: 0, territory: TERRITORY }],
+        ..Level::sandbox()
+    });
+    (game, models::porch_position(models::default_house_positions()[0]))
+}
+
+#[test]
+fn a_dog_catches_a_paperboy_in_its_territory() {
+    let (mut game, kennel) = game_with_dog();
+    let paperboy = game.selected_paperboy();
+    game.get_mut::<Transform>(paperboy).translation = (kennel + Vec2::new(TERRITORY / 2., 0.)).extend(0.);
+    let papers = game.get::<Bag>(paperboy).papers;
+
+    game.advance(2.);
+    assert_eq!(game.get::<Bag>(paperboy).papers, papers - 3);
+    let dog = game.single::<Dog>();
+    assert_eq!(game.get::<Dog>(dog).state, DogState::Returning);
+
+    // then it goes home and calms down
+    game.get_mut::<Transform>(paperboy).translation = (kennel + Vec2::new(0., -TERRITORY * 2.)).extend(0.);
+    game.advance(5.);
+    assert_eq!(game.get::<Dog>(dog).state, DogState::Home);
+    assert_eq!(game.get::<Transform>(dog).translation.truncate(), kennel);
+}
+
+#[test]
+fn paperboys_outside_the_territory_are_left_alone() {
+    let (mut game, kennel) = game_with_dog();
+    let paperboy = game.selected_paperboy();
+    game.get_mut::<Transform>(paperboy).translation = (kennel + Vec2::new(0., -TERRITORY * 2.)).extend(0.);
+    let papers = game.get::<Bag>(paperboy).papers;
+
+    game.advance(2.);
+    let dog = game.single::<Dog>();
+    assert_eq!(game.get::<Dog>(dog).state, DogState::Home);
+    assert_eq!(game.get::<Transform>(dog).translation.truncate(), kennel);
+    assert_eq!(game.get::<Bag>(paperboy).papers, papers);
+    assert!(game.app.world.resource::<GameWorld>().graph.edge_weights().any(|edge| edge.guarded));
+}
+
+#[test]
+fn routes_keep_out_of_dog_territory_where_they_can() {
+    // C to H is as far by D as by B, as laid out in `graph::create_graph`
+    let mut world = graph::create_graph();
+    let walker = Vehicle::new(VehicleKind::Walker);
+    let (b, c, d, h) = (NodeIndex::new(1), NodeIndex::new(2), NodeIndex::new(3), NodeIndex::new(7));
+    let length = |from: NodeIndex, to: NodeIndex| {
+        graph::world_position(&world.graph[from]).distance(graph::world_position(&world.graph[to]))
+    };
+    assert_eq!(length(c, d) + length(d, h), length(c, b) + length(b, h));
+
+    for (from, to) in [(c, d), (d, c)] {
+        let edge = world.graph.find_edge(from, to).unwrap();
+        world.graph[edge].guarded = true;
+    }
+    let (_, nodes) = routing::find_route(&world, c, h, &walker).unwrap();
+    assert_eq!(nodes, vec![c, b, h]);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use bevy::prelude::*;
+
+use common::Harness;
+use parallel_paperboy::economy::Bag;
+use parallel_paperboy::models::{self, House, Order};
+use parallel_paperboy::movement::Route;
+use parallel_paperboy::scoring::Score;
+use parallel_paperboy::{Path, PathSegment, SelectionMode, UIState, PAPERBOY_REACH};
+
+/// Gives `house` an order and stands the selected paperboy `distance` below its porch
+fn order_and_approach(game: &mut Harness, house: Entity, distance: f32) {
+    let now = game.app.world.resource::<Time>().elapsed_seconds();
+    game.get_mut::<House>(house).orders.push(Order { deadline: now + models::ORDER_DEADLINE, subscription: false });
+    let porch = models::porch_position(game.get::<Transform>(house).translation.truncate());
+    let paperboy = game.selected_paperboy();
+    game.get_mut::<Transform>(paperboy).translation = (porch - Vec2::new(0., distance)).extend(0.);
+}
+
+fn first_house(game: &mut Harness) -> Entity {
+    game.app.world.query_filtered::<Entity, With<House>>().iter(&game.app.world).next().unwrap()
+}
+
+#[test]
+fn clicking_in_path_mode_extends_the_path() {
+    let mut game = Harness::quiet_sandbox();
+    game.press(KeyCode::Tab);
+    let ui_state = game.single::<UIState>();
+    assert_eq!(game.get::<UIState>(ui_state).selection_mode, SelectionMode::PlacingPath);
+
+    game.click(Vec2::new(0., 0.));
+    let path = game.single::<Path>();
+    assert_eq!(game.get::<Path>(path).points, vec![Vec2::new(0., 0.)]);
+    assert_eq!(game.count::<PathSegment>(), 0);
+
+    game.click(Vec2::new(100., 50.));
+    assert_eq!(game.get::<Path>(path).points, vec![Vec2::new(0., 0.), Vec2::new(100., 50.)]);
+    assert_eq!(game.count::<PathSegment>(), 1);
+}
+
+#[test]
+fn clicking_in_paperboy_mode_leaves_the_path_alone() {
+    let mut game = Harness::quiet_sandbox();
+    game.click(Vec2::new(0., 0.));
+    let path = game.single::<Path>();
+    assert!(game.get::<Path>(path).points.is_empty());
+    assert_eq!(game.count::<PathSegment>(), 0);
+}
+
+#[test]
+fn delivering_within_reach_serves_the_house() {
+    let mut game = Harness::quiet_sandbox();
+    let house = first_house(&mut game);
+    order_and_approach(&mut game, house, PAPERBOY_REACH / 2.);
+    assert!(game.get::<House>(house).is_active());
+
+    game.press(KeyCode::Space);
+    game.advance(3.);
+    assert!(!game.get::<House>(house).is_active());
+    assert_eq!(game.app.world.resource::<Score>().deliveries, 1);
+}
+
+#[test]
+fn delivering_out_of_reach_throws_nothing() {
+    let mut game = Harness::quiet_sandbox();
+    let house = first_house(&mut game);
+    order_and_approach(&mut game, house, PAPERBOY_REACH * 2.);
+
+    game.press(KeyCode::Space);
+    game.advance(3.);
+    assert!(game.get::<House>(house).is_active());
+    assert_eq!(game.app.world.resource::<Score>().deliveries, 0);
+}
+
+#[test]
+fn auto_throw_serves_an_order_placed_after_a_delivery() {
+    let mut game = Harness::quiet_sandbox();
+    let house = first_house(&mut game);
+    order_and_approach(&mut game, house, PAPERBOY_REACH / 2.);
+    let paperboy = game.selected_paperboy();
+    // pacing back and forth in front of the porch, so the route lasts both orders
+    let stand = game.get::<Transform>(paperboy).translation.truncate();
+    let waypoints = (0..2000).map(|i| stand + Vec2::new((i % 2) as f32 * 2., 0.)).collect();
+    game.app.world.entity_mut(paperboy).insert(Route::new(waypoints));
+    game.get_mut::<House>(house).assigned_to = Some(paperboy);
+    let papers = game.get::<Bag>(paperboy).papers;
+
+    game.advance(3.);
+    assert_eq!(game.app.world.resource::<Score>().deliveries, 1);
+
+    let now = game.app.world.resource::<Time>().elapsed_seconds();
+    let mut ordering = game.get_mut::<House>(house);
+    ordering.orders.push(Order { deadline: now + models::ORDER_DEADLINE, subscription: false });
+    ordering.assigned_to = Some(paperboy);
+    game.advance(3.);
+    assert!(game.app.world.get::<Route>(paperboy).is_some(), "the route ran out before the second order");
+    assert_eq!(game.app.world.resource::<Score>().deliveries, 2);
+    assert_eq!(game.get::<Bag>(paperboy).papers, papers - 2);
+}
+
+#[test]
+fn the_clock_only_moves_with_frames() {
+    let mut game = Harness::quiet_sandbox();
+    let before = game.app.world.resource::<Time>().elapsed();
+    game.frames(60);
+    let after = game.app.world.resource::<Time>().elapsed();
+    assert_eq!(after - before, common::FRAME * 60);
+}
@@ -0,0 +1,28 @@
+use std::fs;
+
+use parallel_paperboy::levels::Campaign;
+use parallel_paperboy::path_limits::PathLimits;
+
+#[test]
+fn levels_from_before_weather_closures_dogs_and_path_limits_still_load() {
+    let path = std::env::temp_dir().join(format!("parallel-paperboy-campaign-{}.ron", std::process::id()));
+    let old = "(levels: [(
+        name: \"Old\",
+        map: \"\",
+        target_score: 10,
+        paperboys: 1,
+        shift_length: 120.0,
+        orders: [],
+        order_policy: OnePerHouse,
+        mechanics: [Dispatch],
+    )])";
+    fs::write(&path, old).unwrap();
+    let campaign = Campaign::load(&path);
+    fs::remove_file(&path).unwrap();
+
+    let level = &campaign.levels[0];
+    assert_eq!(level.name, "Old");
+    assert!(level.subscriptions.is_empty() && level.daylight.is_empty() && level.weather.is_empty());
+    assert!(level.closures.is_empty() && level.dogs.is_empty());
+    assert_eq!(level.path_limits, PathLimits::default());
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use bevy::prelude::*;
+
+use common::Harness;
+use parallel_paperboy::graph::GameWorld;
+use parallel_paperboy::levels::Level;
+use parallel_paperboy::map::MapData;
+use parallel_paperboy::models::{self, House, Order};
+use parallel_paperboy::reload::MapReport;
+
+const SUBURB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/maps/suburb.ron");
+
+/// A copy of the suburb map in a file of its own, and a game playing on it
+fn game_on_copy(name: &str) -> (Harness, PathBuf, MapData) {
+    let map = MapData::load(std::path::Path::new(SUBURB)).unwrap();
+    let path = std::env::temp_dir().join(format!("parallel-paperboy-{}-{}.ron", name, std::process::id()));
+    fs::write(&path, ron::to_string(&map).unwrap()).unwrap();
+    let level = Level { map: path.to_string_lossy().into_owned(), orders: vec![], ..Level::sandbox() };
+    (Harness::new(level), path, map)
+}
+
+/// Writes `contents` over the map file, marked as changed later than anything before it
+fn edit(path: &PathBuf, contents: &str, edits: u64) {
+    fs::write(path, contents).unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(edits)).unwrap();
+}
+
+fn house_at(game: &mut Harness, position: Vec2) -> Option<Entity> {
+    game.app.world.query_filtered::<(Entity, &Transform), With<House>>().iter(&game.app.world)
+        .find(|(_, transform)| transform.translation.truncate() == position)
+        .map(|(entity, _)| entity)
+}
+
+#[test]
+fn editing_the_map_rebuilds_it_in_place() {
+    let (mut game, path, mut map) = game_on_copy("edit");
+    let kept = house_at(&mut game, map.houses[0]).unwrap();
+    game.get_mut::<House>(kept).orders.push(Order { deadline: models::ORDER_DEADLINE, subscription: false });
+    let paperboy = game.selected_paperboy();
+    let position = game.get::<Transform>(paperboy).translation;
+
+    let removed = map.houses.pop().unwrap();
+    map.houses.push(Vec2::new(300., 200.));
+    map.graph.edges.retain(|edge| *edge != (8, 5) && *edge != (5, 8));
+    edit(&path, &ron::to_string(&map).unwrap(), 1);
+    game.advance(1.);
+
+    assert_eq!(game.app.world.resource::<GameWorld>().graph.edge_count(), map.graph.edges.len());
+    assert_eq!(game.count::<House>(), map.houses.len());
+    assert!(house_at(&mut game, removed).is_none());
+    assert!(house_at(&mut game, Vec2::new(300., 200.)).is_some());
+    assert!(game.get::<House>(kept).is_active());
+    assert_eq!(game.get::<Transform>(paperboy).translation, position);
+    // cutting off node 8 is allowed, and shown
+    assert_eq!(game.app.world.resource::<MapReport>().problems, vec!["node 8 can't be reached from the depot".to_string()]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn a_broken_map_is_reported_and_the_old_one_kept() {
+    let (mut game, path, map) = game_on_copy("broken");
+    edit(&path, "(name: \"Suburb\", graph: (", 1);
+    game.advance(1.);
+
+    let problems = &game.app.world.resource::<MapReport>().problems;
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("could not reload"));
+    assert_eq!(game.app.world.resource::<GameWorld>().graph.edge_count(), map.graph.edges.len());
+    assert_eq!(game.count::<House>(), map.houses.len());
+
+    // fixing it clears the report
+    edit(&path, &ron::to_string(&map).unwrap(), 2);
+    game.advance(1.);
+    assert!(game.app.world.resource::<MapReport>().problems.is_empty());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn paperboys_left_off_the_roads_go_back_to_the_depot() {
+    let (mut game, path, map) = game_on_copy("stranded");
+    let paperboy = game.selected_paperboy();
+    game.get_mut::<Transform>(paperboy).translation = Vec3::new(1000., 1000., 0.);
+
+    edit(&path, &ron::to_string(&map).unwrap(), 1);
+    game.advance(1.);
+    assert_eq!(game.get::<Transform>(paperboy).translation.truncate(), map.spawn);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn maps_from_before_footpaths_and_rest_points_still_load() {
+    let path = std::env::temp_dir().join(format!("parallel-paperboy-old-{}.ron", std::process::id()));
+    let old = "(name: \"old\", graph: (nodes: [(0.0, 0.0), (1.0, 0.0)], edges: [(0, 1), (1, 0)]), houses: [], spawn: (0.0, 0.0))";
+    fs::write(&path, old).unwrap();
+    let map = MapData::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(map.graph.footpaths.is_empty());
+    assert!(map.rest_points.is_empty());
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use rand::rngs::mock::StepRng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use parallel_paperboy::levels::Level;
+use parallel_paperboy::orders::{self, OrderEvent, OrderProcess};
+
+const SHIFT_LENGTH: f32 = 300.0;
+
+/// A slow start ramping up to a rush, with bursts throughout
+fn processes() -> Vec<OrderProcess> {
+    vec![
+        OrderProcess::Steady { rate: vec![(0., 2.), (150., 10.), (300., 4.)] },
+        OrderProcess::Rush { at: 200., width: 20., peak: 12. },
+        OrderProcess::Burst { rate: 1.5, size: 4, radius: 200. },
+    ]
+}
+
+/// Events per shift the rates predict between `from` and `to` seconds
+fn expected_events(processes: &[OrderProcess], from: f32, to: f32) -> f32 {
+    let steps = 1000;
+    let step = (to - from) / steps as f32;
+    (0..steps)
+        .map(|i| orders::total_rate(processes, from + (i as f32 + 0.5) * step) * step / 60.)
+        .sum()
+}
+
+#[test]
+fn events_come_at_the_total_rate() {
+    let processes = processes();
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let shifts = 400;
+    let mut times = vec![];
+    for _ in 0..shifts {
+        times.extend(orders::simulate(&processes, SHIFT_LENGTH, &mut rng).into_iter().map(|(t, _)| t));
+    }
+
+    // the quiet start and the rush each match their own share, not only the shift as a whole
+    for (from, to) in [(0., 100.), (100., 180.), (180., 220.), (220., SHIFT_LENGTH), (0., SHIFT_LENGTH)] {
+        let counted = times.iter().filter(|t| **t >= from && **t < to).count() as f32 / shifts as f32;
+        let expected = expected_events(&processes, from, to);
+        assert!((counted - expected).abs() < expected * 0.05, "{} events from {}s to {}s, expected {}", counted, from, to, expected);
+    }
+}
+
+#[test]
+fn there_are_no_gaps_past_the_end_of_the_shift() {
+    let processes = processes();
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    for _ in 0..1000 {
+        let now = rng.gen_range(0.0..SHIFT_LENGTH);
+        if let Some(gap) = orders::next_gap(&processes, now, SHIFT_LENGTH, &mut rng) {
+            assert!(gap > 0. && now + gap <= SHIFT_LENGTH, "{}s after {}s", gap, now);
+        }
+        assert_eq!(orders::next_gap(&processes, SHIFT_LENGTH, SHIFT_LENGTH, &mut rng), None);
+    }
+
+    let silent = [OrderProcess::Steady { rate: vec![(0., 0.)] }, OrderProcess::Burst { rate: 0., size: 3, radius: 100. }];
+    assert_eq!(orders::next_gap(&silent, 0., SHIFT_LENGTH, &mut rng), None);
+    assert_eq!(orders::next_gap(&[], 0., SHIFT_LENGTH, &mut rng), None);
+}
+
+#[test]
+fn processes_without_a_rate_are_never_picked() {
+    // singles stop at a minute in, leaving only the bursts
+    let processes = [
+        OrderProcess::Steady { rate: vec![(0., 6.), (60., 6.), (61., 0.)] },
+        OrderProcess::Rush { at: 100., width: 10., peak: 0. },
+        OrderProcess::Burst { rate: 2., size: 3, radius: 150. },
+    ];
+    let burst = OrderEvent::Burst { size: 3, radius: 150. };
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    for _ in 0..1000 {
+        let elapsed = rng.gen_range(61.0..SHIFT_LENGTH);
+        assert_eq!(orders::pick_event(&processes, elapsed, &mut rng), burst, "at {}s", elapsed);
+    }
+    // the lowest possible draw, which would land on the first process however small its share
+    assert_eq!(orders::pick_event(&processes, 120., &mut StepRng::new(0, 0)), burst);
+    // and the highest
+    assert_eq!(orders::pick_event(&processes, 120., &mut StepRng::new(u64::MAX, 0)), burst);
+}
+
+#[test]
+fn bursts_keep_to_their_size_and_radius() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let candidates: Vec<(Entity, Vec2)> = (0..60)
+        .map(|index| (Entity::from_raw(index), Vec2::new(rng.gen_range(0.0..1000.), rng.gen_range(0.0..1000.))))
+        .collect();
+    let position = |entity: Entity| candidates.iter().find(|(candidate, _)| *candidate == entity).unwrap().1;
+
+    for _ in 0..200 {
+        let size = rng.gen_range(1..8);
+        let radius = rng.gen_range(0.0..400.);
+        let burst = orders::burst_houses(&candidates, size, radius, &mut rng);
+        assert!(!burst.is_empty() && burst.len() <= size as usize, "{} houses for size {}", burst.len(), size);
+        let mut unique = burst.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), burst.len());
+
+        // the first house is the center, the rest are the closest within the radius of it
+        let center = position(burst[0]);
+        let distances: Vec<f32> = burst.iter().map(|house| position(*house).distance(center)).collect();
+        assert!(distances.iter().all(|distance| *distance <= radius), "{:?} past {}", distances, radius);
+        let within = candidates.iter().filter(|(_, position)| position.distance(center) <= radius).count();
+        assert_eq!(burst.len(), within.min(size as usize));
+        let farthest = distances.iter().copied().fold(0., f32::max);
+        let left_out = candidates.iter().filter(|(entity, _)| !burst.contains(entity));
+        assert!(left_out.clone().all(|(_, position)| position.distance(center) >= farthest));
+    }
+
+    assert!(orders::burst_houses(&[], 3, 100., &mut rng).is_empty());
+}
+
+#[test]
+fn the_plot_counts_every_simulated_order() {
+    let level = Level { orders: processes(), shift_length: 95., ..Level::sandbox() };
+    let events = orders::simulate(&level.orders, level.shift_length, &mut ChaCha8Rng::seed_from_u64(5));
+    let total: u32 = events.iter()
+        .map(|(_, event)| match event {
+            OrderEvent::Single => 1,
+            OrderEvent::Burst { size, .. } => *size,
+        })
+        .sum();
+
+    let plot = orders::plot_schedule(&level, &mut ChaCha8Rng::seed_from_u64(5));
+    let lines: Vec<&str> = plot.lines().collect();
+    // a title, a header, ten buckets of 10s with the last one cut short, and the totals
+    assert_eq!(lines.len(), 13, "{}", plot);
+    assert!(lines[11].trim_start().starts_with("90-95"), "{}", lines[11]);
+    let bucket_orders: u32 = lines[2..12].iter().map(|line| line.split_whitespace().nth(1).unwrap().parse::<u32>().unwrap()).sum();
+    assert_eq!(bucket_orders, total);
+    assert_eq!(lines[12], format!("{} events, {} orders in total", events.len(), total));
+}
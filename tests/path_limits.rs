@@ -0,0 +1,84 @@
+mod common;
+
+use bevy::prelude::*;
+
+use common::Harness;
+use parallel_paperboy::levels::Level;
+use parallel_paperboy::models::{self, House, Order};
+use parallel_paperboy::movement::Route;
+use parallel_paperboy::path_limits::{PathCheck, PathLimits, PathViolation};
+use parallel_paperboy::Path;
+
+#[test]
+fn segments_past_the_length_limit_are_too_long() {
+    let limits = PathLimits { max_length: Some(150.), ..default() };
+    let points = [Vec2::ZERO, Vec2::new(100., 0.), Vec2::new(100., 100.)];
+    assert_eq!(limits.check(&points), vec![None, Some(PathViolation::TooLong)]);
+    assert_eq!(PathLimits::default().check(&points), vec![None, None]);
+}
+
+#[test]
+fn segments_past_the_waypoint_limit_are_too_many() {
+    let limits = PathLimits { max_waypoints: Some(3), ..default() };
+    let points = [Vec2::ZERO, Vec2::new(50., 0.), Vec2::new(100., 0.), Vec2::new(150., 0.)];
+    assert_eq!(limits.check(&points[..3]), vec![None, None]);
+    assert_eq!(limits.check(&points), vec![None, None, Some(PathViolation::TooManyWaypoints)]);
+}
+
+#[test]
+fn both_segments_of_a_crossing_are_marked() {
+    let limits = PathLimits { no_self_crossing: true, ..default() };
+    // a bow tie, the first segment crossing the third
+    let points = [Vec2::ZERO, Vec2::new(100., 100.), Vec2::new(100., 0.), Vec2::new(0., 100.)];
+    let crossing = Some(PathViolation::SelfCrossing);
+    assert_eq!(limits.check(&points), vec![crossing, None, crossing]);
+    assert_eq!(PathLimits::default().check(&points), vec![None, None, None]);
+}
+
+#[test]
+fn dispatch_is_refused_until_the_path_keeps_to_the_limits() {
+    let mut game = Harness::new(Level {
+        orders: vec![],
+        path_limits: PathLimits { max_waypoints: Some(2), ..default() },
+        ..Level::sandbox()
+    });
+    let paperboy = game.selected_paperboy();
+    game.press(KeyCode::Tab);
+    for point in [Vec2::new(0., 0.), Vec2::new(100., 0.), Vec2::new(100., 100.)] {
+        game.click(point);
+    }
+    assert!(!game.app.world.resource::<PathCheck>().is_ok());
+
+    game.press(KeyCode::Return);
+    assert!(game.app.world.get::<Route>(paperboy).is_none());
+
+    game.press(KeyCode::Z);
+    assert!(game.app.world.resource::<PathCheck>().is_ok());
+    game.press(KeyCode::Return);
+    assert!(game.app.world.get::<Route>(paperboy).is_some());
+}
+
+#[test]
+fn planned_paths_can_always_be_dispatched() {
+    let limits = [
+        PathLimits { max_waypoints: Some(4), ..default() },
+        PathLimits { max_length: Some(600.), ..default() },
+        PathLimits { no_self_crossing: true, ..default() },
+        PathLimits { max_length: Some(2000.), max_waypoints: Some(16), no_self_crossing: true },
+    ];
+    for path_limits in limits {
+        let mut game = Harness::new(Level { orders: vec![], path_limits: path_limits.clone(), ..Level::sandbox() });
+        let houses: Vec<Entity> = game.app.world.query_filtered::<Entity, With<House>>().iter(&game.app.world).collect();
+        for house in houses {
+            game.get_mut::<House>(house).orders.push(Order { deadline: models::ORDER_DEADLINE, subscription: false });
+        }
+        let paperboy = game.selected_paperboy();
+
+        game.press(KeyCode::R);
+        let path = game.single::<Path>();
+        assert!(!game.get::<Path>(path).points.is_empty(), "nothing planned within {:?}", path_limits);
+        assert!(game.app.world.resource::<PathCheck>().is_ok(), "planned past {:?}", path_limits);
+        game.press(KeyCode::Return);
+        assert!(game.app.world.get::<Route>(paperboy).is_some(), "not dispatched within {:?}", path_limits);
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use parallel_paperboy::graph::GraphData;
+use parallel_paperboy::save::{self, SaveState};
+
+fn state(rng_word_pos: u128) -> SaveState {
+    SaveState {
+        level: 0,
+        graph: GraphData { nodes: vec![Vec2::ZERO, Vec2::X], edges: vec![(0, 1), (1, 0)], footpaths: vec![] },
+        houses: vec![],
+        paperboys: vec![],
+        papers: vec![],
+        path: vec![],
+        delivery_timer_duration: 2.,
+        delivery_timer_elapsed: 0.5,
+        score: 3,
+        deliveries: 1,
+        late_deliveries: 0,
+        lateness: 0.,
+        wages: 0,
+        tips: 0,
+        shift_elapsed: 10.,
+        auto_throw: true,
+        rng_seed: 7,
+        rng_word_pos,
+    }
+}
+
+#[test]
+fn the_rng_position_is_saved_exactly() {
+    let path = std::env::temp_dir().join(format!("parallel-paperboy-save-{}.ron", std::process::id()));
+    // past what 64 bits can hold
+    let saved = state((1 << 100) + 7);
+    save::write_save(&path, &saved).unwrap();
+    let loaded = save::read_save(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, Ok(saved));
+}
+
+#[test]
+fn older_saves_are_still_migrated() {
+    let path = std::env::temp_dir().join(format!("parallel-paperboy-old-save-{}.ron", std::process::id()));
+    let saved = state(12345);
+    save::write_save(&path, &saved).unwrap();
+    // nothing this state has changed since the version before
+    let contents = std::fs::read_to_string(&path).unwrap()
+        .replace(&format!("version: {}", save::SAVE_VERSION), &format!("version: {}", save::SAVE_VERSION - 1));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(save::parse_save(&contents), Ok(saved));
+}
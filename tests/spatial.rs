@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::utils::FloatOrd;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use parallel_paperboy::spatial::{Shape, SpatialGrid, CELL_SIZE};
+
+const SIDE: f32 = 1000.0;
+
+/// Points, and segments up to a few cells long, scattered over a square `SIDE` across
+fn random_shapes(rng: &mut ChaCha8Rng, count: usize) -> Vec<Shape> {
+    (0..count)
+        .map(|index| {
+            let start = Vec2::new(rng.gen_range(0.0..SIDE), rng.gen_range(0.0..SIDE));
+            if index % 2 == 0 {
+                Shape::Point(start)
+            } else {
+                let reach = CELL_SIZE * 4.;
+                Shape::Segment(start, start + Vec2::new(rng.gen_range(-reach..reach), rng.gen_range(-reach..reach)))
+            }
+        })
+        .collect()
+}
+
+fn grid_of(shapes: &[Shape]) -> SpatialGrid<usize> {
+    let mut grid = SpatialGrid::new(CELL_SIZE);
+    for (index, shape) in shapes.iter().enumerate() {
+        grid.insert(index, *shape);
+    }
+    grid
+}
+
+/// The distances to the `k` nearest shapes `accept` lets through, found by looking at every one
+fn scan_nearest(shapes: &[Shape], point: Vec2, k: usize, accept: impl Fn(usize) -> bool) -> Vec<f32> {
+    let mut distances: Vec<f32> = shapes.iter()
+        .enumerate()
+        .filter(|(index, _)| accept(*index))
+        .map(|(_, shape)| shape.distance(point))
+        .collect();
+    distances.sort_by_key(|distance| FloatOrd(*distance));
+    distances.truncate(k);
+    distances
+}
+
+fn distances(found: &[(usize, f32)]) -> Vec<f32> {
+    found.iter().map(|(_, distance)| *distance).collect()
+}
+
+fn queries(rng: &mut ChaCha8Rng) -> Vec<Vec2> {
+    let mut queries: Vec<Vec2> = (0..200).map(|_| Vec2::new(rng.gen_range(0.0..SIDE), rng.gen_range(0.0..SIDE))).collect();
+    // well away from every occupied cell
+    queries.extend([Vec2::new(-3000., -3000.), Vec2::new(SIDE * 4., SIDE / 2.), Vec2::new(SIDE / 2., -SIDE * 3.)]);
+    queries
+}
+
+#[test]
+fn nearest_agrees_with_a_scan() {
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let shapes = random_shapes(&mut rng, 300);
+    let grid = grid_of(&shapes);
+    for point in queries(&mut rng) {
+        for k in [1, 3, 10] {
+            assert_eq!(distances(&grid.nearest(point, k, |_| true)), scan_nearest(&shapes, point, k, |_| true), "k={} at {}", k, point);
+        }
+        let odd = |index: usize| index % 2 == 1;
+        assert_eq!(distances(&grid.nearest(point, 4, odd)), scan_nearest(&shapes, point, 4, odd), "odd only at {}", point);
+    }
+}
+
+#[test]
+fn nearest_returns_everything_when_asked_for_more_than_there_is() {
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let shapes = random_shapes(&mut rng, 7);
+    let grid = grid_of(&shapes);
+    let point = Vec2::new(-500., SIDE * 2.);
+    assert_eq!(distances(&grid.nearest(point, 20, |_| true)), scan_nearest(&shapes, point, 20, |_| true));
+    assert!(grid.nearest(point, 0, |_| true).is_empty());
+}
+
+#[test]
+fn within_agrees_with_a_scan() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let shapes = random_shapes(&mut rng, 300);
+    let grid = grid_of(&shapes);
+    for point in queries(&mut rng) {
+        for radius in [10., 120., 450.] {
+            let mut found: Vec<usize> = grid.within(point, radius).iter().map(|(index, _)| *index).collect();
+            found.sort();
+            let scanned: Vec<usize> = (0..shapes.len()).filter(|index| shapes[*index].distance(point) <= radius).collect();
+            assert_eq!(found, scanned, "radius {} at {}", radius, point);
+        }
+    }
+}
+
+#[test]
+fn a_segment_is_found_from_every_cell_it_crosses() {
+    let mut grid = SpatialGrid::new(CELL_SIZE);
+    grid.insert(0, Shape::Segment(Vec2::new(0., 50.), Vec2::new(CELL_SIZE * 5., 50.)));
+    for cell in 0..5 {
+        let point = Vec2::new(CELL_SIZE * (cell as f32 + 0.5), 60.);
+        assert_eq!(grid.within(point, 15.), vec![(0, 10.)]);
+        assert_eq!(grid.nearest(point, 1, |_| true), vec![(0, 10.)]);
+    }
+}
+
+#[test]
+fn a_cleared_grid_only_finds_what_was_inserted_since() {
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let mut grid = grid_of(&random_shapes(&mut rng, 100));
+    grid.clear();
+    assert!(grid.is_empty());
+    assert!(grid.nearest(Vec2::ZERO, 1, |_| true).is_empty());
+
+    // somewhere else entirely, so the old bounds would be no help
+    let shapes: Vec<Shape> = random_shapes(&mut rng, 50).iter()
+        .map(|shape| match *shape {
+            Shape::Point(point) => Shape::Point(point + Vec2::splat(SIDE * 5.)),
+            Shape::Segment(start, end) => Shape::Segment(start + Vec2::splat(SIDE * 5.), end + Vec2::splat(SIDE * 5.)),
+        })
+        .collect();
+    for (index, shape) in shapes.iter().enumerate() {
+        grid.insert(index, *shape);
+    }
+    assert_eq!(grid.len(), shapes.len());
+    for point in queries(&mut rng) {
+        assert_eq!(distances(&grid.nearest(point, 5, |_| true)), scan_nearest(&shapes, point, 5, |_| true));
+        assert!(grid.within(point, 300.).iter().all(|(index, distance)| *distance == shapes[*index].distance(point)));
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::PathBuf;
+
+use parallel_paperboy::economy::Profile;
+use parallel_paperboy::leaderboard::{Leaderboard, LeaderboardEntry};
+
+/// An empty directory of its own for a test's files
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("parallel-paperboy-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Names of the files in `directory` starting with `prefix`
+fn files_starting(directory: &PathBuf, prefix: &str) -> Vec<String> {
+    fs::read_dir(directory).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+#[test]
+fn a_corrupt_profile_is_kept_and_its_backup_restored() {
+    let directory = directory("profile");
+    let path = directory.join("profile.ron");
+    let earlier = Profile { money: 40, ..Profile::default() };
+    earlier.save(&path).unwrap();
+    Profile { money: 55, ..Profile::default() }.save(&path).unwrap();
+    fs::write(&path, "(money: 5").unwrap();
+
+    assert_eq!(Profile::load(&path), earlier);
+    let corrupt = files_starting(&directory, "profile.ron.corrupt-");
+    assert_eq!(corrupt.len(), 1);
+    assert_eq!(fs::read_to_string(directory.join(&corrupt[0])).unwrap(), "(money: 5");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn a_corrupt_leaderboard_is_kept_and_its_backup_restored() {
+    let directory = directory("leaderboard");
+    let path = directory.join("leaderboard.ron");
+    let entry = |score| LeaderboardEntry {
+        map: "grid".to_string(),
+        seed: 7,
+        score,
+        deliveries: 3,
+        late_deliveries: 1,
+        lateness: 2.5,
+        date: 1_700_000_000,
+    };
+    let earlier = Leaderboard { entries: vec![entry(120), entry(90)] };
+    earlier.save(&path).unwrap();
+    Leaderboard { entries: vec![entry(120), entry(90), entry(150)] }.save(&path).unwrap();
+    fs::write(&path, "(entries: [(map: \"grid\"").unwrap();
+
+    assert_eq!(Leaderboard::load(&path), earlier);
+    assert_eq!(files_starting(&directory, "leaderboard.ron.corrupt-").len(), 1);
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use parallel_paperboy::throwing;
+use parallel_paperboy::vehicle::{Vehicle, VehicleKind};
+
+/// Average angle, in radians, by which papers thrown at `speed` miss the line to their target
+fn average_aim_error(speed: f32) -> f32 {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let target = Vec2::new(100., 0.);
+    let throws = 1000;
+    let total: f32 = (0..throws)
+        .map(|_| throwing::launch_velocity(Vec2::ZERO, target, speed, &mut rng).angle_between(target).abs())
+        .sum();
+    total / throws as f32
+}
+
+#[test]
+fn faster_vehicles_throw_less_accurately() {
+    let errors: Vec<f32> = [VehicleKind::Walker, VehicleKind::Bicycle, VehicleKind::Scooter].into_iter()
+        .map(|kind| average_aim_error(Vehicle::new(kind).max_speed))
+        .collect();
+    assert_eq!(average_aim_error(0.), 0.);
+    assert!(errors[0] < errors[1] && errors[1] < errors[2], "{:?}", errors);
+}
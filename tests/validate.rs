@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::utils::petgraph::graph::NodeIndex;
+
+use parallel_paperboy::graph::{self, EdgeKind, GameWorld, GraphData};
+use parallel_paperboy::map::MapData;
+use parallel_paperboy::models;
+use parallel_paperboy::validate::{validate_map, Problem};
+
+/// Both ways along each of `roads`
+fn both_ways(roads: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    roads.iter().flat_map(|(start, end)| [(*start, *end), (*end, *start)]).collect()
+}
+
+/// A square block of roads, nodes 0 to 3 counterclockwise from the origin
+fn block() -> GraphData {
+    GraphData {
+        nodes: vec![Vec2::new(0., 0.), Vec2::new(1., 0.), Vec2::new(1., 1.), Vec2::new(0., 1.)],
+        edges: both_ways(&[(0, 1), (1, 2), (2, 3), (3, 0)]),
+        footpaths: vec![],
+    }
+}
+
+/// `graph` as a map, with the depot at its first node and `houses` in world coordinates
+fn map_of(graph: GraphData, houses: Vec<Vec2>) -> MapData {
+    let spawn = node_position(&graph, 0);
+    MapData { name: "test".to_string(), graph, houses, spawn, rest_points: vec![] }
+}
+
+fn node_position(graph: &GraphData, node: usize) -> Vec2 {
+    graph::world_position(&GameWorld::from(graph).graph[NodeIndex::new(node)])
+}
+
+fn problems(graph: GraphData) -> Vec<Problem> {
+    validate_map(&map_of(graph, vec![]))
+}
+
+#[test]
+fn a_block_of_two_way_roads_has_no_problems() {
+    assert_eq!(problems(block()), vec![]);
+}
+
+#[test]
+fn a_map_without_roads_is_reported() {
+    assert_eq!(problems(GraphData { edges: vec![], ..block() }), vec![Problem::NoRoads]);
+}
+
+#[test]
+fn nodes_in_the_same_place_are_duplicates() {
+    let mut graph = block();
+    graph.nodes.push(Vec2::new(1., 1.));
+    graph.edges.extend(both_ways(&[(3, 4)]));
+    assert!(problems(graph).contains(&Problem::DuplicateNodes { first: 2, second: 4 }));
+}
+
+#[test]
+fn edges_between_the_same_place_have_no_length() {
+    let mut graph = block();
+    graph.edges.push((2, 2));
+    assert!(problems(graph).contains(&Problem::ZeroLengthEdge { edge: (2, 2) }));
+}
+
+#[test]
+fn edges_without_one_back_are_one_way() {
+    let mut graph = block();
+    graph.edges.retain(|edge| *edge != (1, 0));
+    graph.footpaths.push((0, 2));
+    let problems = problems(graph);
+    assert!(problems.contains(&Problem::OneWay { edge: (0, 1), kind: EdgeKind::Road }));
+    assert!(problems.contains(&Problem::OneWay { edge: (0, 2), kind: EdgeKind::Footpath }));
+}
+
+#[test]
+fn edges_along_each_other_overlap() {
+    let mut graph = block();
+    graph.nodes.push(Vec2::new(2., 0.));
+    graph.edges.extend(both_ways(&[(0, 4)]));
+    assert!(problems(graph).contains(&Problem::Overlap { first: (0, 1), second: (0, 4) }));
+
+    // and the same edge twice overlaps itself
+    let mut graph = block();
+    graph.edges.push((0, 1));
+    assert!(problems(graph).contains(&Problem::Overlap { first: (0, 1), second: (0, 1) }));
+}
+
+#[test]
+fn edges_crossing_without_a_node_are_reported() {
+    let mut graph = block();
+    graph.edges.extend(both_ways(&[(0, 2), (1, 3)]));
+    assert!(problems(graph).contains(&Problem::Crossing { first: (0, 2), second: (1, 3), at: Vec2::new(0.5, 0.5) }));
+}
+
+#[test]
+fn nodes_and_houses_off_the_network_are_unreachable() {
+    // a road of its own, away from the block
+    let mut graph = block();
+    graph.nodes.extend([Vec2::new(5., 0.), Vec2::new(6., 0.), Vec2::new(5., 5.)]);
+    graph.edges.extend(both_ways(&[(4, 5)]));
+    let road = node_position(&graph, 4).lerp(node_position(&graph, 5), 0.5);
+    let on_block = node_position(&graph, 0).lerp(node_position(&graph, 1), 0.5);
+    // houses stand behind their porches, which face the road
+    let house_by = |road: Vec2| road + (road - models::porch_position(road));
+    let problems = validate_map(&map_of(graph, vec![house_by(on_block), house_by(road)]));
+
+    for node in [4, 5, 6] {
+        assert!(problems.contains(&Problem::UnreachableNode { node }), "node {}", node);
+    }
+    assert!(problems.contains(&Problem::UnreachableHouse { house: 1 }));
+    assert!(!problems.contains(&Problem::UnreachableHouse { house: 0 }));
+}
+
+#[test]
+fn the_bundled_maps_pass() {
+    for path in ["assets/maps/grid_town.ron", "assets/maps/suburb.ron"] {
+        let map = MapData::load(Path::new(path)).unwrap();
+        assert_eq!(validate_map(&map), vec![], "{}", path);
+    }
+}